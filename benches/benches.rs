@@ -25,9 +25,9 @@ mod wpb {
     use std::thread;
     use std::sync::Arc;
     use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::{Once, ONCE_INIT};
+    use std::sync::Once;
 
-    static ONCE: Once = ONCE_INIT;
+    static ONCE: Once = Once::new();
     const THREADS_QTY: usize = 4;
     const FOO_LOGGERS_QTY: usize = 10;
 
@@ -37,7 +37,7 @@ mod wpb {
     }
 
     impl LThreads {
-        fn new(f: Arc<Fn() + Sync + Send>) -> Self {
+        fn new(f: Arc<dyn Fn() + Sync + Send>) -> Self {
             let mut threads = Vec::new();
             let stop = Arc::new(AtomicBool::new(false));
             for _ in 0..THREADS_QTY {
@@ -51,8 +51,8 @@ mod wpb {
                 }));
             }
             LThreads {
-                threads: threads,
-                stop: stop,
+                threads,
+                stop,
             }
         }
 
@@ -110,6 +110,7 @@ mod wpb {
         ONCE.call_once(|| {
             let config = wp::Config {
                 thread: cfg!(feature = "test-thread-log"),
+                ..Default::default()
             };
             wp_init!(&config);
         });
@@ -118,11 +119,11 @@ mod wpb {
     }
 
     fn drop_output() {
-        wp_register_handler!(Box::new(|_| {}));
+        wp_register_handler!(Box::new(|_: &dyn wp::Record| {}));
     }
 
     fn drop_formatted_output() {
-        wp_register_handler!(Box::new(|r| {
+        wp_register_handler!(Box::new(|r: &dyn wp::Record| {
             let _ = r.formatted();
         }));
     }
@@ -12,9 +12,94 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use formatters::timestamp::TsStyle;
+
 /// Initial logger configuration.
+///
+/// New options might be added in the future releases,
+/// so build the configuration with the setters starting from the defaults
+/// rather than with a struct literal listing all of the fields.
+/// A struct literal ending with `..Default::default()` keeps compiling as well.
+///
+/// Note that in 0.4 the struct had the only `thread` field,
+/// hence the `Config { thread: true }` literals must be updated.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!(&wp::Config::default().thread(true).app_name("myapp"));
+///
+///     assert_eq!(wp::meta::get().app_name, "myapp");
+/// }
+///
+/// ```
 #[derive(Default)]
 pub struct Config {
     /// Log thread switch.
     pub thread: bool,
+    /// Emit the records of the log thread in the order of their
+    /// [sequence numbers](record/trait.Record.html#method.seq).
+    ///
+    /// Otherwise the log thread consumes the records of different threads
+    /// in a round-robin manner and they might be reordered.
+    pub ordered: bool,
+    /// Name of the application.
+    ///
+    /// See the [meta](meta/index.html) module for the details.
+    pub app_name: Option<String>,
+    /// Version of the application.
+    pub app_version: Option<String>,
+    /// Style of the timestamps.
+    pub timestamp: TsStyle,
+    /// Match the paths of the log rules as plain string prefixes.
+    ///
+    /// By default a rule applies only at the `::` boundaries of the module path
+    /// and at the `/` boundaries of the file path,
+    /// e.g. the rule for `foo` applies to `foo::bar` but not to `foobar::baz`.
+    ///
+    /// This option restores the behaviour of the previous versions.
+    pub prefix_match: bool,
+}
+
+impl Config {
+    /// Sets the [thread](#structfield.thread) switch.
+    pub fn thread(mut self, thread: bool) -> Self {
+        self.thread = thread;
+        self
+    }
+
+    /// Sets the [ordered](#structfield.ordered) switch.
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// Sets the [name](#structfield.app_name) of the application.
+    pub fn app_name(mut self, name: &str) -> Self {
+        self.app_name = Some(name.to_string());
+        self
+    }
+
+    /// Sets the [version](#structfield.app_version) of the application.
+    pub fn app_version(mut self, version: &str) -> Self {
+        self.app_version = Some(version.to_string());
+        self
+    }
+
+    /// Sets the [style](#structfield.timestamp) of the timestamps.
+    pub fn timestamp(mut self, style: TsStyle) -> Self {
+        self.timestamp = style;
+        self
+    }
+
+    /// Sets the [prefix_match](#structfield.prefix_match) switch.
+    pub fn prefix_match(mut self, prefix_match: bool) -> Self {
+        self.prefix_match = prefix_match;
+        self
+    }
 }
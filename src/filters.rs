@@ -0,0 +1,141 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Log record filters.
+//!
+//! The filters see each log record before it's pushed into the handlers
+//! and either pass it on, possibly with a different log level, or drop it.
+//!
+//! Any closure that takes a log record as an argument and returns a [Verdict](enum.Verdict.html)
+//! is a log record filter.
+//!
+//! The filters run in the order of registration.
+//! The record is dropped as soon as any filter rejects it.
+//! The rewritten log level is seen by the rest of the filters, the handlers and the formatters,
+//! however the record isn't checked against the module log level once again.
+//!
+//! # Example
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate woodpecker;
+//! use woodpecker as wp;
+//!
+//! use wp::filters::Verdict;
+//!
+//! use std::sync::{Arc, Mutex};
+//! use std::ops::Deref;
+//!
+//! fn main() {
+//!     wp_init!();
+//!
+//!     wp_register_filter!(Box::new(|record: &dyn wp::Record| {
+//!         if record.msg().starts_with("GET /health") {
+//!             Verdict::Reject
+//!         } else if record.msg().contains("timeout") {
+//!             Verdict::Level(wp::LogLevel::ERROR)
+//!         } else {
+//!             Verdict::Accept
+//!         }
+//!     }));
+//!
+//!     let out = Arc::new(Mutex::new(String::new()));
+//!     {
+//!         let out = out.clone();
+//!         wp_register_handler!(Box::new(move |record: &dyn wp::Record| {
+//!             let msg = format!("{}:{}|", record.level(), record.msg().deref());
+//!             out.lock().unwrap().push_str(&msg);
+//!         }));
+//!     }
+//!
+//!     warn!("GET /health");
+//!     warn!("GET /users timeout");
+//!     warn!("GET /users");
+//!
+//!     if cfg!(feature = "test-thread-log") {
+//!         wp::sync();
+//!     }
+//!
+//!     assert_eq!(*out.lock().unwrap(), "ERROR:GET /users timeout|WARN:GET /users|");
+//! }
+//!
+//! ```
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use formatters::Formatter;
+use handlers;
+use levels::LogLevel;
+use record::Record;
+use record::imp::Amended;
+use stats;
+
+/// The decision of a log record filter.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Verdict {
+    /// Pass the log record on.
+    Accept,
+    /// Drop the log record.
+    Reject,
+    /// Pass the log record on with the given log level.
+    Level(LogLevel),
+}
+
+/// Log record filter.
+pub trait Filter: Send + Sync {
+    /// Decides what to do with the log record.
+    fn filter(&self, record: &dyn Record) -> Verdict;
+}
+
+impl<F> Filter for F where F: Fn(&dyn Record) -> Verdict + Send + Sync {
+    #[inline(always)]
+    fn filter(&self, record: &dyn Record) -> Verdict {
+        self(record)
+    }
+}
+
+/// Runs the filters over the log record.
+///
+/// Returns the log level of the record unless it's rejected.
+pub(crate) fn run(filters: &[Arc<dyn Filter>], record: &dyn Record, formatter: &Arc<Formatter>) -> Option<LogLevel> {
+    let mut level = record.level();
+    for filter in filters {
+        let verdict = if level == record.level() {
+            filter.filter(record)
+        } else {
+            filter.filter(&Amended::new(record, level, record.msg(), formatter))
+        };
+        match verdict {
+            Verdict::Accept => {},
+            Verdict::Reject => return None,
+            Verdict::Level(new) => level = new,
+        }
+    }
+    Some(level)
+}
+
+/// Runs the filters over the log record.
+///
+/// A panic in the filter is reported into stderr and the record is passed on as is.
+pub(crate) fn guard(filters: &[Arc<dyn Filter>], record: &dyn Record, formatter: &Arc<Formatter>) -> Option<LogLevel> {
+    match panic::catch_unwind(AssertUnwindSafe(|| run(filters, record, formatter))) {
+        Ok(level) => level,
+        Err(err) => {
+            stats::handler_error();
+            handlers::stderr::emit(&format!("Log filter panicked: {}\n", handlers::describe(err.as_ref())));
+            Some(record.level())
+        },
+    }
+}
@@ -12,20 +12,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::borrow::Cow;
+
 use record::Record;
 
+/// Returns the name of the thread that produced the record or its identifier.
+pub fn thread(record: &dyn Record) -> Cow<'_, str> {
+    match record.thread_name() {
+        Some(name) => Cow::Borrowed(name),
+        None => Cow::Owned(record.thread_id().to_string()),
+    }
+}
+
 /// A default log formatter which outputs the record in as a line.
 ///
 /// # Example:
 ///
 /// ```ignore
-/// |<log-level>| time module@file:line <message>
+/// |<log-level>| timestamp module@file:line <message>
 /// ```
-pub fn formatter(record: &Record) -> String {
+///
+/// The timestamp is formatted according to the [timestamp style](../timestamp/index.html).
+///
+/// Use the [pattern](../pattern/index.html) formatter to print e.g. the `{thread}` of the record.
+pub fn formatter(record: &dyn Record) -> String {
     format!(
         concat!("|{}| {} {}", wp_separator!(), "{}:{} {}\n"),
         record.level(),
-        record.ts_utc(),
+        record.ts_formatted(),
         record.module(),
         record.file(),
         record.line(),
@@ -17,7 +17,13 @@ use record::Record;
 /// Type of the formatting function.
 ///
 /// Returns a formatted log record.
-pub type Formatter = Box<Fn(&Record) -> String + Send + Sync>;
+pub type Formatter = Box<dyn Fn(&dyn Record) -> String + Send + Sync>;
 
 /// Default log formatter.
 pub mod default;
+
+/// Pattern based log formatter.
+pub mod pattern;
+
+/// Timestamp formatting.
+pub mod timestamp;
@@ -0,0 +1,242 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! A log formatter defined by a pattern string.
+//!
+//! The pattern is a text with placeholders enclosed in braces:
+//!
+//! * `{level}` - log level
+//! * `{ts}` - timestamp formatted according to the [timestamp style](../timestamp/index.html)
+//! * `{elapsed}` - time elapsed since init, e.g. `+12.345ms`
+//! * `{delta}` - time elapsed since the previous record of the thread, e.g. `+0.042ms`
+//! * `{seq}` - sequence number of the record
+//! * `{module}` - module path
+//! * `{file}` - file path
+//! * `{line}` - line number
+//! * `{thread}` - name of the thread or its identifier if the thread isn't named
+//! * `{thread_id}` - identifier of the thread
+//! * `{thread_name}` - name of the thread, empty if the thread isn't named
+//! * `{msg}` - user log message
+//! * `{pid}` - process identifier
+//! * `{hostname}` - name of the host
+//! * `{app}` - name of the application
+//! * `{app_version}` - version of the application
+//!
+//! See the [meta](../../meta/index.html) module for the details on the process metadata.
+//!
+//! The braces are escaped as `{{` and `}}`.
+//!
+//! A newline is appended to every formatted record.
+//!
+//! # Example
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate woodpecker;
+//! use woodpecker as wp;
+//!
+//! fn main() {
+//!     wp_init!();
+//!
+//!     let formatter = wp::formatters::pattern::formatter("{level} [{thread}] {module}: {msg}").unwrap();
+//!     wp_set_formatter!(formatter);
+//!
+//!     warn!("It's alive!");
+//!     wp::sync();
+//! }
+//!
+//! ```
+
+use std::fmt::Write;
+
+use formatters::Formatter;
+use formatters::default;
+use formatters::timestamp::Delta;
+use meta;
+use record::Record;
+
+/// The errors that might occur during parsing of the pattern.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PatternError {
+    /// Unknown placeholder.
+    UnknownKey(String),
+    /// The placeholder starting at the given position isn't closed.
+    Unclosed(usize),
+    /// Unmatched closing brace at the given position.
+    Unmatched(usize),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Text(String),
+    Level,
+    Ts,
+    Elapsed,
+    Delta,
+    Seq,
+    Module,
+    File,
+    Line,
+    Thread,
+    ThreadId,
+    ThreadName,
+    Msg,
+    Pid,
+    Hostname,
+    App,
+    AppVersion,
+}
+
+impl Token {
+    fn from_key(key: &str) -> Result<Self, PatternError> {
+        Ok(match key {
+            "level" => Token::Level,
+            "ts" => Token::Ts,
+            "elapsed" => Token::Elapsed,
+            "delta" => Token::Delta,
+            "seq" => Token::Seq,
+            "module" => Token::Module,
+            "file" => Token::File,
+            "line" => Token::Line,
+            "thread" => Token::Thread,
+            "thread_id" => Token::ThreadId,
+            "thread_name" => Token::ThreadName,
+            "msg" => Token::Msg,
+            "pid" => Token::Pid,
+            "hostname" => Token::Hostname,
+            "app" => Token::App,
+            "app_version" => Token::AppVersion,
+            _ => return Err(PatternError::UnknownKey(key.to_string())),
+        })
+    }
+
+    fn write(&self, out: &mut String, record: &dyn Record) {
+        let _ = match *self {
+            Token::Text(ref text) => {
+                out.push_str(text);
+                Ok(())
+            },
+            Token::Level => write!(out, "{}", record.level()),
+            Token::Ts => write!(out, "{}", record.ts_formatted()),
+            Token::Elapsed => write!(out, "{}", Delta(record.elapsed())),
+            Token::Delta => write!(out, "{}", Delta(record.delta())),
+            Token::Seq => write!(out, "{}", record.seq()),
+            Token::Module => write!(out, "{}", record.module()),
+            Token::File => write!(out, "{}", record.file()),
+            Token::Line => write!(out, "{}", record.line()),
+            Token::Thread => write!(out, "{}", default::thread(record)),
+            Token::ThreadId => write!(out, "{}", record.thread_id()),
+            Token::ThreadName => write!(out, "{}", record.thread_name().unwrap_or("")),
+            Token::Msg => write!(out, "{}", record.msg()),
+            Token::Pid => write!(out, "{}", meta::get().pid),
+            Token::Hostname => write!(out, "{}", meta::get().hostname),
+            Token::App => write!(out, "{}", meta::get().app_name),
+            Token::AppVersion => write!(out, "{}", meta::get().app_version),
+        };
+    }
+}
+
+fn parse(pattern: &str) -> Result<Vec<Token>, PatternError> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut chars = pattern.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek().map(|&(_, n)| n) == Some(c) => {
+                chars.next();
+                text.push(c);
+            },
+            '{' => {
+                let mut key = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, c)) => key.push(c),
+                        None => return Err(PatternError::Unclosed(pos)),
+                    }
+                }
+                if !text.is_empty() {
+                    tokens.push(Token::Text(text.clone()));
+                    text.clear();
+                }
+                tokens.push(Token::from_key(key.trim())?);
+            },
+            '}' => return Err(PatternError::Unmatched(pos)),
+            _ => text.push(c),
+        }
+    }
+    text.push('\n');
+    tokens.push(Token::Text(text));
+
+    Ok(tokens)
+}
+
+/// Creates a formatter that formats the log records according to the pattern.
+///
+/// See the [module](index.html) documentation for the list of supported placeholders.
+pub fn formatter(pattern: &str) -> Result<Formatter, PatternError> {
+    let tokens = parse(pattern)?;
+    Ok(Box::new(move |record: &dyn Record| {
+        let mut out = String::with_capacity(128);
+        for token in &tokens {
+            token.write(&mut out, record);
+        }
+        out
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use levels::LogLevel;
+    use record::imp::{RecordMeta, RecordContext, SyncRecord};
+
+    static RECORD: RecordMeta = RecordMeta::new(LogLevel::INFO, "foo::bar", "foo/bar.rs", "foo::bar@foo/bar.rs", 42);
+
+    fn format(pattern: &str) -> String {
+        let formatter = formatter(pattern).unwrap();
+        let dummy: Arc<Formatter> = Arc::new(Box::new(|_| String::new()));
+        formatter(&SyncRecord::new(&RECORD, RecordContext::new(7, 3), format_args!("{}", "msg"), dummy))
+    }
+
+    #[test]
+    fn test_pattern() {
+        assert_eq!(format("{level}|{module}|{file}:{line}|{seq}|{msg}"), "INFO|foo::bar|foo/bar.rs:42|7|msg\n");
+        assert_eq!(format("{{{ msg }}}"), "{msg}\n");
+        assert_eq!(format("{thread_id}"), "3\n");
+        assert!(format("{delta}").starts_with('+'));
+        assert_eq!(format("{pid}"), format!("{}\n", ::std::process::id()));
+
+        let out = thread::Builder::new()
+            .name("worker".to_string())
+            .spawn(|| format("{thread}|{thread_name}"))
+            .unwrap().join().unwrap();
+        assert_eq!(out, "worker|worker\n");
+        let out = thread::spawn(|| format("{thread}|{thread_name}")).join().unwrap();
+        assert_eq!(out, "3|\n");
+    }
+
+    #[test]
+    fn test_pattern_invalid() {
+        assert_eq!(parse("{foo}").err(), Some(PatternError::UnknownKey("foo".to_string())));
+        assert_eq!(parse("ab{msg").err(), Some(PatternError::Unclosed(2)));
+        assert_eq!(parse("ab}").err(), Some(PatternError::Unmatched(2)));
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Formatting of the timestamps of the log records.
+//!
+//! The timestamp of a record is formatted according to the
+//! [style](struct.TsStyle.html) once and cached within the record.
+//! See [Record::ts_formatted](../../record/trait.Record.html#method.ts_formatted).
+//!
+//! The style is set via the [configuration](../../struct.Config.html) on init
+//! or with the [set_style](fn.set_style.html) function.
+//!
+//! # Example
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate woodpecker;
+//! use woodpecker as wp;
+//!
+//! use wp::formatters::timestamp::{TsStyle, TsFormat};
+//!
+//! fn main() {
+//!     wp_init!(&wp::Config {
+//!         timestamp: TsStyle {
+//!             format: TsFormat::Strftime("%H:%M:%S%.3f".to_string()),
+//!             local: true,
+//!         },
+//!         ..Default::default()
+//!     });
+//!
+//!     warn!("It's alive!");
+//!     wp::sync();
+//! }
+//!
+//! ```
+
+extern crate chrono;
+use self::chrono::prelude::*;
+
+extern crate parking_lot;
+use self::parking_lot::RwLock;
+
+use std::fmt;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The amount of fractional digits of the seconds.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Precision {
+    /// Whole seconds.
+    Seconds,
+    /// Three digits.
+    Millis,
+    /// Six digits.
+    Micros,
+    /// Nine digits.
+    Nanos,
+}
+
+/// The format of the timestamp.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum TsFormat {
+    /// The `Display` output of the timestamp, e.g. `2017-05-14 18:12:42.251123456 UTC`.
+    ///
+    /// This is the default.
+    Display,
+    /// [RFC 3339](https://tools.ietf.org/html/rfc3339) with the given precision,
+    /// e.g. `2017-05-14T18:12:42.251+00:00`.
+    Rfc3339(Precision),
+    /// Milliseconds since the Unix epoch.
+    EpochMillis,
+    /// A [strftime](https://docs.rs/chrono/0.3/chrono/format/strftime/index.html)-like format string.
+    ///
+    /// The output is truncated at the first invalid format specifier.
+    Strftime(String),
+}
+
+/// The style of the timestamp.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TsStyle {
+    /// Format of the timestamp.
+    pub format: TsFormat,
+    /// Use the local time zone instead of UTC.
+    pub local: bool,
+}
+
+impl Default for TsStyle {
+    fn default() -> Self {
+        TsStyle {
+            format: TsFormat::Display,
+            local: false,
+        }
+    }
+}
+
+fn format<Tz: TimeZone>(ts: &DateTime<Tz>, format: &TsFormat) -> String where Tz::Offset: fmt::Display {
+    let mut out = String::with_capacity(32);
+    let _ = match *format {
+        TsFormat::Display => write!(out, "{}", ts),
+        TsFormat::Rfc3339(precision) => {
+            let fmt = match precision {
+                Precision::Seconds => "%Y-%m-%dT%H:%M:%S%:z",
+                Precision::Millis => "%Y-%m-%dT%H:%M:%S%.3f%:z",
+                Precision::Micros => "%Y-%m-%dT%H:%M:%S%.6f%:z",
+                Precision::Nanos => "%Y-%m-%dT%H:%M:%S%.9f%:z",
+            };
+            write!(out, "{}", ts.format(fmt))
+        },
+        TsFormat::EpochMillis => {
+            write!(out, "{}", ts.timestamp() * 1000 + i64::from(ts.timestamp_subsec_millis()))
+        },
+        TsFormat::Strftime(ref fmt) => write!(out, "{}", ts.format(fmt)),
+    };
+    out
+}
+
+impl TsStyle {
+    /// Formats the timestamp according to the style.
+    pub fn format(&self, ts: &DateTime<UTC>) -> String {
+        if self.local {
+            format(&ts.with_timezone(&Local), &self.format)
+        } else {
+            format(ts, &self.format)
+        }
+    }
+}
+
+/// Displays a duration in milliseconds with a leading `+`, e.g. `+12.345ms`.
+///
+/// Handy for printing the [relative](../../record/trait.Record.html#method.delta) timestamps.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Delta(pub Duration);
+
+impl fmt::Display for Delta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let micros = self.0.as_micros();
+        write!(f, "+{}.{:03}ms", micros / 1000, micros % 1000)
+    }
+}
+
+lazy_static! {
+    static ref STYLE: RwLock<Arc<TsStyle>> = RwLock::new(Arc::new(TsStyle::default()));
+}
+
+/// Sets the style of the timestamps.
+///
+/// The records which timestamp is already formatted aren't affected.
+pub fn set_style(style: TsStyle) {
+    *STYLE.write() = Arc::new(style);
+}
+
+/// Returns the style of the timestamps.
+pub fn get_style() -> Arc<TsStyle> {
+    STYLE.read().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts() -> DateTime<UTC> {
+        UTC.ymd(2017, 5, 14).and_hms_nano(18, 12, 42, 251_123_456)
+    }
+
+    fn style(format: TsFormat) -> TsStyle {
+        TsStyle {
+            format,
+            local: false,
+        }
+    }
+
+    #[test]
+    fn test_ts_format() {
+        let ts = ts();
+        assert_eq!(TsStyle::default().format(&ts), ts.to_string());
+        assert_eq!(style(TsFormat::Display).format(&ts), "2017-05-14 18:12:42.251123456 UTC");
+        assert_eq!(style(TsFormat::Rfc3339(Precision::Seconds)).format(&ts), "2017-05-14T18:12:42+00:00");
+        assert_eq!(style(TsFormat::Rfc3339(Precision::Millis)).format(&ts), "2017-05-14T18:12:42.251+00:00");
+        assert_eq!(style(TsFormat::Rfc3339(Precision::Micros)).format(&ts), "2017-05-14T18:12:42.251123+00:00");
+        assert_eq!(style(TsFormat::Rfc3339(Precision::Nanos)).format(&ts), "2017-05-14T18:12:42.251123456+00:00");
+        assert_eq!(style(TsFormat::EpochMillis).format(&ts), "1494785562251");
+        assert_eq!(style(TsFormat::Strftime("%d/%m/%Y %H:%M".to_string())).format(&ts), "14/05/2017 18:12");
+        assert_eq!(style(TsFormat::Strftime("%H:%M %Q".to_string())).format(&ts), "18:12 ");
+
+        let local = TsStyle {
+            format: TsFormat::Rfc3339(Precision::Seconds),
+            local: true,
+        };
+        let expected = ts.with_timezone(&Local).format("%Y-%m-%dT%H:%M:%S%:z").to_string();
+        assert_eq!(local.format(&ts), expected);
+    }
+
+    #[test]
+    fn test_delta() {
+        assert_eq!(Delta(Duration::from_secs(0)).to_string(), "+0.000ms");
+        assert_eq!(Delta(Duration::from_micros(12_345)).to_string(), "+12.345ms");
+        assert_eq!(Delta(Duration::new(2, 5_000)).to_string(), "+2000.005ms");
+    }
+}
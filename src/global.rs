@@ -12,17 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::atomic::{AtomicIsize, AtomicBool, Ordering,
-                        ATOMIC_ISIZE_INIT, ATOMIC_BOOL_INIT};
+use std::sync::atomic::{AtomicIsize, AtomicBool, Ordering};
 
 use levels::LogLevel;
 
-static LOG_LEVEL: AtomicIsize = ATOMIC_ISIZE_INIT;
-static HAS_SUBLOGGERS: AtomicBool = ATOMIC_BOOL_INIT;
+static LOG_LEVEL: AtomicIsize = AtomicIsize::new(0);
+static HAS_SUBLOGGERS: AtomicBool = AtomicBool::new(false);
 
 #[inline(always)]
 pub fn get_level() -> LogLevel {
-    LogLevel::from(LOG_LEVEL.load(Ordering::Relaxed))
+    LogLevel::from_value(LOG_LEVEL.load(Ordering::Relaxed))
 }
 
 pub fn set_level(level: LogLevel) {
@@ -0,0 +1,255 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+extern crate parking_lot;
+use self::parking_lot::Mutex;
+
+use std::io::Write;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use std::thread;
+
+use handlers::Handler;
+use levels::LogLevel;
+use record::Record;
+use stats;
+
+/// Defines when the buffered log records are pushed into the log sink.
+///
+/// The buffer is always flushed on [sync](../../fn.sync.html).
+#[derive(Clone, Debug)]
+pub struct FlushPolicy {
+    /// Flush once the buffer holds at least this amount of bytes.
+    pub size: usize,
+    /// Flush the buffer periodically.
+    ///
+    /// Intervals shorter than 10 milliseconds are rounded up.
+    pub interval: Option<Duration>,
+    /// Flush immediately the records at or above this level.
+    pub level: Option<LogLevel>,
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        FlushPolicy {
+            size: 8 * 1024,
+            interval: Some(Duration::from_millis(1000)),
+            level: Some(LogLevel::ERROR),
+        }
+    }
+}
+
+// Keeps the flusher thread from spinning on the tiny intervals.
+const MIN_INTERVAL: Duration = Duration::from_millis(10);
+
+struct Buffer<W: Write> {
+    writer: W,
+    buf: Vec<u8>,
+    policy: FlushPolicy,
+}
+
+impl<W: Write> Buffer<W> {
+    fn new(writer: W, policy: FlushPolicy) -> Self {
+        Buffer {
+            writer,
+            buf: Vec::with_capacity(policy.size),
+            policy,
+        }
+    }
+
+    fn emit(&mut self, level: LogLevel, msg: &[u8]) {
+        self.buf.extend_from_slice(msg);
+        let urgent = match self.policy.level {
+            Some(flevel) => level >= flevel,
+            None => false,
+        };
+        if urgent || self.buf.len() >= self.policy.size {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            if self.writer.write_all(&self.buf).is_err() {
+                stats::handler_error();
+            }
+            self.buf.clear();
+        }
+        if self.writer.flush().is_err() {
+            stats::handler_error();
+        }
+    }
+}
+
+struct Buffered<W: Write> {
+    buffer: Arc<Mutex<Buffer<W>>>,
+}
+
+impl<W: Write + Send> Handler for Buffered<W> {
+    fn emit(&self, record: &dyn Record) {
+        let mut buffer = self.buffer.lock();
+        buffer.emit(record.level(), record.formatted().as_bytes());
+    }
+
+    fn flush(&self) {
+        let mut buffer = self.buffer.lock();
+        buffer.flush();
+    }
+}
+
+impl<W: Write> Drop for Buffered<W> {
+    fn drop(&mut self) {
+        let mut buffer = self.buffer.lock();
+        buffer.flush();
+    }
+}
+
+fn tick<W: Write>(buffer: Weak<Mutex<Buffer<W>>>, interval: Duration) {
+    loop {
+        thread::sleep(interval);
+        match buffer.upgrade() {
+            Some(buffer) => {
+                let mut buffer = buffer.lock();
+                if !buffer.buf.is_empty() {
+                    buffer.flush();
+                }
+            },
+            None => break,
+        }
+    }
+}
+
+/// Pushes formatted log records into the writer through a buffer.
+///
+/// The buffer is flushed according to the given [policy](struct.FlushPolicy.html).
+///
+/// If the flush interval is defined the buffer is flushed periodically in a dedicated thread.
+pub fn handler<W: Write + Send + 'static>(writer: W, policy: FlushPolicy) -> Box<dyn Handler> {
+    let interval = policy.interval;
+    let buffer = Arc::new(Mutex::new(Buffer::new(writer, policy)));
+    if let Some(interval) = interval {
+        let buffer = Arc::downgrade(&buffer);
+        thread::spawn(move || tick(buffer, interval.max(MIN_INTERVAL)));
+    }
+    Box::new(Buffered {
+        buffer,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use formatters::Formatter;
+    use record::imp::{RecordMeta, RecordContext, SyncRecord};
+
+    #[derive(Clone)]
+    struct Sink {
+        out: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.out.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn sink() -> (Sink, Arc<Mutex<Vec<u8>>>) {
+        let out = Arc::new(Mutex::new(Vec::new()));
+        (Sink { out: out.clone() }, out)
+    }
+
+    #[test]
+    fn test_buffered() {
+        let (writer, out) = sink();
+        let policy = FlushPolicy {
+            size: 4,
+            interval: None,
+            level: Some(LogLevel::ERROR),
+        };
+        let mut buffer = Buffer::new(writer, policy);
+
+        buffer.emit(LogLevel::INFO, b"ab");
+        assert!(out.lock().is_empty());
+        buffer.emit(LogLevel::INFO, b"cd");
+        assert_eq!(out.lock().as_slice(), b"abcd");
+
+        buffer.emit(LogLevel::INFO, b"e");
+        assert_eq!(out.lock().as_slice(), b"abcd");
+        buffer.emit(LogLevel::ERROR, b"f");
+        assert_eq!(out.lock().as_slice(), b"abcdef");
+
+        buffer.emit(LogLevel::INFO, b"g");
+        buffer.flush();
+        assert_eq!(out.lock().as_slice(), b"abcdefg");
+    }
+
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::other("broken"))
+        }
+    }
+
+    #[test]
+    fn test_buffered_errors() {
+        let policy = FlushPolicy {
+            size: 1024,
+            interval: None,
+            level: None,
+        };
+        let mut buffer = Buffer::new(Broken, policy);
+
+        let errors = stats::stats().handler_errors;
+        buffer.emit(LogLevel::INFO, b"a");
+        buffer.flush();
+        assert!(stats::stats().handler_errors >= errors + 2);
+        assert!(buffer.buf.is_empty());
+    }
+
+    #[test]
+    fn test_buffered_interval() {
+        static RECORD: RecordMeta = RecordMeta::new(LogLevel::INFO, "foo", "foo.rs", "foo@foo.rs", 42);
+
+        let (writer, out) = sink();
+        let policy = FlushPolicy {
+            size: 1024,
+            interval: Some(Duration::from_millis(0)),
+            level: None,
+        };
+        let handler = handler(writer, policy);
+
+        let formatter: Arc<Formatter> = Arc::new(Box::new(|record| record.msg().to_string()));
+        handler.emit(&SyncRecord::new(&RECORD, RecordContext::new(0, 0), format_args!("x"), formatter));
+        for _ in 0..100 {
+            if !out.lock().is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(out.lock().as_slice(), b"x");
+    }
+}
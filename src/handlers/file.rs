@@ -21,6 +21,9 @@ use std::io;
 use std::io::Write;
 
 use handlers::Handler;
+use handlers::buffered::{self, FlushPolicy};
+use record::Record;
+use stats;
 
 /// The errors that might occur during creation of the handler.
 #[derive(Debug)]
@@ -50,22 +53,52 @@ impl Context {
     }
 
     fn emit(&mut self, msg: &[u8]) {
-        let _ = self.file.write_all(msg);
+        if self.file.write_all(msg).is_err() {
+            stats::handler_error();
+        }
+    }
+
+    fn flush(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+struct FileHandler {
+    ctx: Mutex<Context>,
+}
+
+impl Handler for FileHandler {
+    fn emit(&self, record: &dyn Record) {
+        let mut ctx = self.ctx.lock();
+        ctx.emit(record.formatted().as_bytes());
+    }
+
+    fn flush(&self) {
+        let mut ctx = self.ctx.lock();
+        ctx.flush();
     }
 }
 
 /// Pushes formatted log record into a file.
 ///
 /// The directories to the log file are created automatically.
-pub fn handler(path: &Path) -> Result<Handler, FileHandlerError> {
+pub fn handler(path: &Path) -> Result<Box<dyn Handler>, FileHandlerError> {
     let ctx = Context::new(path)?;
-    let ctx = Mutex::new(ctx);
-    Ok(Box::new(move |record| {
-        let mut ctx = ctx.lock();
-        ctx.emit(record.formatted().as_bytes());
+    Ok(Box::new(FileHandler {
+        ctx: Mutex::new(ctx),
     }))
 }
 
+/// Pushes formatted log record into a file through a buffer.
+///
+/// The directories to the log file are created automatically.
+///
+/// See the [buffered](../buffered/fn.handler.html) handler for the details.
+pub fn buffered(path: &Path, policy: FlushPolicy) -> Result<Box<dyn Handler>, FileHandlerError> {
+    let ctx = Context::new(path)?;
+    Ok(buffered::handler(ctx.file, policy))
+}
+
 #[cfg(test)]
 mod tests {
     extern crate tempdir;
@@ -91,7 +124,7 @@ mod tests {
     #[cfg_attr(target_os = "windows", ignore)]
     fn test_file_invalid() {
         let dir = TempDir::new("wp-f").unwrap();
-        let ctx = Context::new(&dir.path());
+        let ctx = Context::new(dir.path());
         let err = ctx.err().unwrap();
 
         assert!(format!("{:?}", err).contains("directory"));
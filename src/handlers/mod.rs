@@ -12,12 +12,86 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
 use record::Record;
+use stats;
+
+/// Log handler.
+///
+/// Pushes log records into a log sink.
+///
+/// Any closure that takes a log record as an argument is a log handler.
+/// The type of the argument can't be inferred when such closure is boxed directly,
+/// i.e. it's either `Box::new(|record: &dyn Record| ...)` or [from_fn](fn.from_fn.html).
+pub trait Handler: Send + Sync {
+    /// Pushes a log record into the log sink.
+    fn emit(&self, record: &dyn Record);
+
+    /// Flushes the records buffered by the handler into the log sink.
+    fn flush(&self) {}
+
+    /// Flushes the buffered records and releases the log sink.
+    ///
+    /// The records pushed after the handler was closed might be dropped.
+    fn close(&self) {
+        self.flush();
+    }
+}
+
+impl<F> Handler for F where F: Fn(&dyn Record) + Send + Sync {
+    #[inline(always)]
+    fn emit(&self, record: &dyn Record) {
+        self(record)
+    }
+}
+
+/// Creates a log handler from the closure.
+///
+/// Unlike `Box::new` it lets the type of the closure argument be inferred.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     wp_register_handler!(wp::handlers::from_fn(|record| {
+///         eprint!("{}", record.formatted());
+///     }));
+///
+///     warn!("It's alive!");
+/// }
+///
+/// ```
+pub fn from_fn<F>(f: F) -> Box<dyn Handler> where F: Fn(&dyn Record) + Send + Sync + 'static {
+    Box::new(f)
+}
+
+pub(crate) fn describe(err: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = err.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = err.downcast_ref::<String>() {
+        msg.as_str()
+    } else {
+        "unknown panic"
+    }
+}
 
-/// Type of the log handlers function.
+/// Pushes a log record into the handler.
 ///
-/// Pushes a log record into a log sink.
-pub type Handler = Box<Fn(&Record) + Send + Sync>;
+/// A panic in the handler is reported into stderr instead of being propagated.
+pub(crate) fn guard(handler: &dyn Handler, record: &dyn Record) {
+    if let Err(err) = panic::catch_unwind(AssertUnwindSafe(|| handler.emit(record))) {
+        stats::handler_error();
+        stderr::emit(&format!("Log handler panicked: {}\n", describe(err.as_ref())));
+    }
+}
 
 /// Stdout log handler.
 pub mod stdout;
@@ -27,3 +101,9 @@ pub mod stderr;
 pub mod file;
 /// Rotating file log handler.
 pub mod rotating_file;
+/// Buffered log handler.
+pub mod buffered;
+/// Threaded log handler.
+pub mod threaded;
+/// Log handler wrappers.
+pub mod wrap;
@@ -21,6 +21,8 @@ use std::io;
 use std::io::Write;
 
 use handlers::Handler;
+use record::Record;
+use stats;
 
 /// The errors that might occur during creation of the handler.
 #[derive(Debug)]
@@ -73,14 +75,16 @@ impl Context {
         Ok(Context {
             path: path.into(),
             logs: Self::logs(path, count),
-            size: size,
+            size,
             current: file.metadata()?.len(),
-            file: file,
+            file,
         })
     }
 
     fn ____emit(&mut self, msg: &[u8]) {
-        let _ = self.file.write_all(msg);
+        if self.file.write_all(msg).is_err() {
+            stats::handler_error();
+        }
         self.current += msg.len() as u64;
     }
 
@@ -101,11 +105,11 @@ impl Context {
                 }
             };
 
-            if res.is_err() {
+            if let Err(err) = res {
                 let msg = {
                     let old = self.logs[i].display();
                     let new = self.logs[i - 1].display();
-                    format!("Failed to rename {} into {}: {}", old, new, res.unwrap_err())
+                    format!("Failed to rename {} into {}: {}", old, new, err)
                 };
                 self.__emit(msg);
             }
@@ -144,7 +148,29 @@ impl Context {
     }
 
     fn emit(&mut self, msg: &[u8]) {
-        let _ = self.emit_check(msg);
+        if self.emit_check(msg).is_err() {
+            stats::handler_error();
+        }
+    }
+
+    fn flush(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+struct RotatingFileHandler {
+    ctx: Mutex<Context>,
+}
+
+impl Handler for RotatingFileHandler {
+    fn emit(&self, record: &dyn Record) {
+        let mut ctx = self.ctx.lock();
+        ctx.emit(record.formatted().as_bytes());
+    }
+
+    fn flush(&self) {
+        let mut ctx = self.ctx.lock();
+        ctx.flush();
     }
 }
 
@@ -157,12 +183,10 @@ impl Context {
 /// Maintains up to `count` log files.
 ///
 /// Each log file after rotation has a numeric suffix.
-pub fn handler(path: &Path, count: usize, size: u64) -> Result<Handler, RotatingFileHandlerError> {
+pub fn handler(path: &Path, count: usize, size: u64) -> Result<Box<dyn Handler>, RotatingFileHandlerError> {
     let ctx = Context::new(path, count, size)?;
-    let ctx = Mutex::new(ctx);
-    Ok(Box::new(move |record| {
-        let mut ctx = ctx.lock();
-        ctx.emit(record.formatted().as_bytes());
+    Ok(Box::new(RotatingFileHandler {
+        ctx: Mutex::new(ctx),
     }))
 }
 
@@ -186,13 +210,12 @@ mod tests {
     }
 
     fn tlogs(logs: &[PathBuf], size: u64, filled: usize) {
-        for i in 0..filled {
-            let log = &logs[i];
+        for log in logs.iter().take(filled) {
             assert!(log.exists());
             assert_eq!(log.metadata().unwrap().len(), size);
         }
-        for i in filled..logs.len() {
-            assert!(!logs[i].exists());
+        for log in logs.iter().skip(filled) {
+            assert!(!log.exists());
         }
     }
 
@@ -245,7 +268,7 @@ mod tests {
     #[cfg_attr(target_os = "windows", ignore)]
     fn test_rotating_file_invalid() {
         let dir = TempDir::new("wp-f").unwrap();
-        let ctx = Context::new(&dir.path(), 1, 1);
+        let ctx = Context::new(dir.path(), 1, 1);
         let err = ctx.err().unwrap();
 
         assert!(format!("{:?}", err).contains("directory"));
@@ -272,29 +295,29 @@ mod tests {
         logs.reverse();
 
         // Fail rename of origin to `.x`
-        setro(&dir.path(), true);
+        setro(dir.path(), true);
         push(&mut ctx, 2);
         for log in &logs {
             assert!(!log.exists());
         }
 
         // Fail rename from `.x` to `.y`
-        setro(&dir.path(), false);
+        setro(dir.path(), false);
         push(&mut ctx, 2);
-        setro(&dir.path(), true);
+        setro(dir.path(), true);
         push(&mut ctx, 2);
         assert!(logs[0].exists());
-        for idx in 1..logs.len() {
-            assert!(!logs[idx].exists());
+        for log in logs.iter().skip(1) {
+            assert!(!log.exists());
         }
         let expect = "Failed to rename".to_string();
         assert!(contains(&path, &expect));
-        for idx in 0..1 {
-            assert!(contains(&logs[idx], &expect));
+        for log in logs.iter().take(1) {
+            assert!(contains(log, &expect));
         }
 
         // Fail to open origin
-        setro(&dir.path(), false);
+        setro(dir.path(), false);
         dir.close().unwrap();
         assert!(push_check(&mut ctx, 2).err().unwrap().contains("Failed to open"));
     }
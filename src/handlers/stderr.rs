@@ -16,6 +16,7 @@ use std::io::{self, Write};
 use std::ops::Deref;
 
 use handlers::Handler;
+use record::Record;
 
 pub(crate) fn emit(formatted: &str) {
     let stderr = io::stderr();
@@ -23,9 +24,19 @@ pub(crate) fn emit(formatted: &str) {
     let _ = stderr.write_all(formatted.as_bytes());
 }
 
-/// Pushes formatted log record into stderr.
-pub fn handler() -> Handler {
-    Box::new(|record| {
+struct Stderr;
+
+impl Handler for Stderr {
+    fn emit(&self, record: &dyn Record) {
         emit(record.formatted().deref());
-    })
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Pushes formatted log record into stderr.
+pub fn handler() -> Box<dyn Handler> {
+    Box::new(Stderr)
 }
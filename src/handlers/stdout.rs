@@ -16,6 +16,8 @@ use std::io::{self, Write};
 use std::ops::Deref;
 
 use handlers::Handler;
+use handlers::buffered::{self, FlushPolicy};
+use record::Record;
 
 pub(crate) fn emit(formatted: &str) {
     let stdout = io::stdout();
@@ -23,9 +25,26 @@ pub(crate) fn emit(formatted: &str) {
     let _ = stdout.write_all(formatted.as_bytes());
 }
 
-/// Pushes formatted log record into stdout.
-pub fn handler() -> Handler {
-    Box::new(|record| {
+struct Stdout;
+
+impl Handler for Stdout {
+    fn emit(&self, record: &dyn Record) {
         emit(record.formatted().deref());
-    })
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Pushes formatted log record into stdout.
+pub fn handler() -> Box<dyn Handler> {
+    Box::new(Stdout)
+}
+
+/// Pushes formatted log record into stdout through a buffer.
+///
+/// See the [buffered](../buffered/fn.handler.html) handler for the details.
+pub fn buffered(policy: FlushPolicy) -> Box<dyn Handler> {
+    buffered::handler(io::stdout(), policy)
 }
@@ -0,0 +1,364 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+extern crate parking_lot;
+use self::parking_lot::{Mutex, Condvar};
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering};
+use std::time::Duration;
+use std::thread;
+use std::io;
+
+use handlers::{self, Handler};
+use record::Record;
+use stats;
+use record::imp::DetachedRecord;
+
+/// The policy applied when the queue of the handler is full.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Overflow {
+    /// Wait until there's space in the queue.
+    Block,
+    /// Drop the record being pushed.
+    DropNewest,
+    /// Drop the oldest record in the queue.
+    DropOldest,
+}
+
+/// The errors that might occur during creation of the handler.
+#[derive(Debug)]
+pub enum ThreadedHandlerError {
+    /// Failed to spawn the handler thread.
+    IoError(io::Error),
+    /// Queue size is invalid.
+    SizeError(usize),
+}
+
+impl From<io::Error> for ThreadedHandlerError {
+    fn from(e: io::Error) -> Self {
+        ThreadedHandlerError::IoError(e)
+    }
+}
+
+struct Queue {
+    records: Mutex<VecDeque<DetachedRecord>>,
+    pushed: Condvar,
+    popped: Condvar,
+    drained: Condvar,
+    size: usize,
+    overflow: Overflow,
+    sent: AtomicUsize,
+    received: AtomicUsize,
+    closed: AtomicBool,
+}
+
+impl Queue {
+    fn new(size: usize, overflow: Overflow) -> Self {
+        Queue {
+            records: Mutex::new(VecDeque::with_capacity(size)),
+            pushed: Condvar::new(),
+            popped: Condvar::new(),
+            drained: Condvar::new(),
+            size,
+            overflow,
+            sent: AtomicUsize::new(0),
+            received: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    fn push(&self, record: DetachedRecord) {
+        let mut records = self.records.lock();
+        loop {
+            // Nobody consumes the records once the queue is closed.
+            if self.closed.load(Ordering::Relaxed) {
+                stats::dropped();
+                return;
+            }
+            if records.len() < self.size {
+                break;
+            }
+            match self.overflow {
+                Overflow::Block => {
+                    self.popped.wait(&mut records);
+                },
+                Overflow::DropNewest => {
+                    stats::dropped();
+                    return;
+                },
+                Overflow::DropOldest => {
+                    records.pop_front();
+                    stats::dropped();
+                    self.consumed();
+                },
+            }
+        }
+        records.push_back(record);
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        self.pushed.notify_one();
+    }
+
+    fn pop(&self) -> Option<DetachedRecord> {
+        let mut records = self.records.lock();
+        loop {
+            if let Some(record) = records.pop_front() {
+                self.popped.notify_one();
+                return Some(record);
+            }
+            if self.closed.load(Ordering::Relaxed) {
+                return None;
+            }
+            self.pushed.wait_for(&mut records, Duration::from_millis(100));
+        }
+    }
+
+    fn empty(&self) -> bool {
+        self.sent.load(Ordering::Relaxed) == self.received.load(Ordering::Relaxed)
+    }
+
+    // Must be called with the records locked.
+    fn consumed(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+        if self.empty() {
+            self.drained.notify_all();
+        }
+    }
+
+    fn wait(&self) {
+        let mut records = self.records.lock();
+        while !self.empty() {
+            self.drained.wait(&mut records);
+        }
+    }
+
+    fn close(&self) {
+        let _records = self.records.lock();
+        self.closed.store(true, Ordering::Relaxed);
+        self.pushed.notify_all();
+        self.popped.notify_all();
+    }
+}
+
+struct Threaded {
+    queue: Arc<Queue>,
+    handler: Arc<dyn Handler>,
+}
+
+impl Handler for Threaded {
+    fn emit(&self, record: &dyn Record) {
+        self.queue.push(DetachedRecord::from(record));
+    }
+
+    fn flush(&self) {
+        self.queue.wait();
+        self.handler.flush();
+    }
+
+    fn close(&self) {
+        self.queue.wait();
+        self.queue.close();
+        self.handler.close();
+    }
+}
+
+impl Drop for Threaded {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+fn run(queue: Arc<Queue>, handler: Arc<dyn Handler>) {
+    while let Some(record) = queue.pop() {
+        handlers::guard(handler.as_ref(), &record);
+        let _records = queue.records.lock();
+        queue.consumed();
+    }
+}
+
+/// Pushes log records into the given handler in a dedicated thread.
+///
+/// The log records are buffered in a queue of up to `size` records.
+/// The `overflow` policy defines what happens when the queue is full.
+///
+/// This way a slow log sink doesn't delay the others.
+///
+/// A panic in the handler is reported into stderr and doesn't stop the thread.
+///
+/// Flushing the handler waits until the queue is consumed.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+/// use wp::handlers::threaded::Overflow;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     let stdout = wp::handlers::stdout::handler();
+///     wp_register_handler!(wp::handlers::threaded::handler(stdout, 1024, Overflow::Block).unwrap());
+///
+///     warn!("It's alive!");
+///     wp::sync();
+/// }
+///
+/// ```
+pub fn handler(handler: Box<dyn Handler>, size: usize, overflow: Overflow)
+               -> Result<Box<dyn Handler>, ThreadedHandlerError> {
+    if size == 0 {
+        return Err(ThreadedHandlerError::SizeError(size));
+    }
+
+    let queue = Arc::new(Queue::new(size, overflow));
+    let handler: Arc<dyn Handler> = Arc::from(handler);
+    {
+        let queue = queue.clone();
+        let handler = handler.clone();
+        thread::Builder::new()
+            .name("woodpecker-handler".to_string())
+            .spawn(move || run(queue, handler))?;
+    }
+
+    Ok(Box::new(Threaded {
+        queue,
+        handler,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate time;
+
+    use super::*;
+
+    use std::sync::Arc;
+    use std::sync::mpsc;
+
+    use formatters::Formatter;
+    use levels::LogLevel;
+    use record::imp::{RecordMeta, RecordContext, SyncRecord};
+
+    static RECORD: RecordMeta = RecordMeta::new(LogLevel::INFO, "foo", "foo.rs", "foo@foo.rs", 42);
+
+    fn push(handler: &dyn Handler, msg: &str) {
+        let formatter: Arc<Formatter> = Arc::new(Box::new(|record| record.msg().to_string()));
+        handler.emit(&SyncRecord::new(&RECORD, RecordContext::new(0, 0), format_args!("{}", msg), formatter));
+    }
+
+    fn tqueue(size: usize, overflow: Overflow, pushed: &[&str], expect: &[&str]) {
+        let queue = Arc::new(Queue::new(size, overflow));
+        let (tx, rx) = mpsc::channel();
+        let handler: Arc<dyn Handler> = Arc::new(move |record: &dyn Record| {
+            let _ = tx.send(record.formatted().to_string());
+        });
+        let sink = Threaded {
+            queue: queue.clone(),
+            handler: handler.clone(),
+        };
+
+        for msg in pushed {
+            push(&sink, msg);
+        }
+        drop(sink);
+        run(queue.clone(), handler);
+
+        assert!(queue.empty());
+        let out: Vec<String> = rx.iter().collect();
+        assert_eq!(out, expect);
+    }
+
+    #[test]
+    fn test_threaded_overflow() {
+        tqueue(2, Overflow::DropNewest, &["a", "b", "c"], &["a", "b"]);
+        tqueue(2, Overflow::DropOldest, &["a", "b", "c"], &["b", "c"]);
+        tqueue(3, Overflow::Block, &["a", "b", "c"], &["a", "b", "c"]);
+    }
+
+    struct Sink {
+        out: Mutex<mpsc::Sender<String>>,
+    }
+
+    impl Handler for Sink {
+        fn emit(&self, record: &dyn Record) {
+            if record.msg().as_str() == "panic" {
+                panic!("boom");
+            }
+            let _ = self.out.lock().send(record.msg().to_string());
+        }
+
+        fn flush(&self) {
+            let _ = self.out.lock().send("flush".to_string());
+        }
+
+        fn close(&self) {
+            let _ = self.out.lock().send("close".to_string());
+        }
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (tx, rx) = mpsc::channel();
+        let inner = Box::new(Sink {
+            out: Mutex::new(tx),
+        });
+        let handler = handler(inner, 1, Overflow::Block).unwrap();
+
+        push(handler.as_ref(), "a");
+        push(handler.as_ref(), "panic");
+        push(handler.as_ref(), "b");
+        handler.flush();
+
+        let out: Vec<String> = rx.try_iter().collect();
+        assert_eq!(out, vec!["a", "b", "flush"]);
+
+        handler.close();
+        // The records emitted after close are dropped rather than blocked.
+        push(handler.as_ref(), "c");
+        push(handler.as_ref(), "d");
+        drop(handler);
+        let out: Vec<String> = rx.iter().collect();
+        assert_eq!(out, vec!["close"]);
+    }
+
+    #[test]
+    fn test_threaded_lazy() {
+        let (tx, rx) = mpsc::channel();
+        let inner = Box::new(move |record: &dyn Record| {
+            let _ = tx.send((record.formatted().to_string(), record.ts_local().timestamp()));
+        });
+        let handler = handler(inner, 1, Overflow::Block).unwrap();
+
+        let formatter: Arc<Formatter> = Arc::new(Box::new(|_| {
+            thread::current().name().unwrap_or_default().to_string()
+        }));
+        let record = SyncRecord::new(&RECORD, RecordContext::new(0, 0), format_args!("foo"), formatter);
+        handler.emit(&record);
+        handler.flush();
+
+        let (formatted, ts) = rx.recv().unwrap();
+        assert_eq!(formatted, "woodpecker-handler");
+        assert_eq!(ts, record.ts().sec);
+    }
+
+    #[test]
+    fn test_threaded_invalid() {
+        let inner = Box::new(|_: &dyn Record| {});
+        let err = handler(inner, 0, Overflow::Block).err().unwrap();
+        assert!(format!("{:?}", err).contains("SizeError"));
+    }
+}
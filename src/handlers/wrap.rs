@@ -0,0 +1,289 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use std::sync::Arc;
+
+use formatters::Formatter;
+use handlers::Handler;
+use levels::LogLevel;
+use logger;
+use path_pattern::{self, PathPattern};
+use record::Record;
+use spec;
+use record::imp::Amended;
+
+struct Level {
+    handler: Box<dyn Handler>,
+    level: LogLevel,
+}
+
+impl Handler for Level {
+    fn emit(&self, record: &dyn Record) {
+        if record.level() >= self.level {
+            self.handler.emit(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.handler.flush();
+    }
+
+    fn close(&self) {
+        self.handler.close();
+    }
+}
+
+/// Pushes into the handler only the log records at or above the given level.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     wp_set_level!(wp::LogLevel::INFO).unwrap();
+///     wp_register_handler!(wp::handlers::stdout::handler());
+///     let stderr = wp::handlers::stderr::handler();
+///     wp_register_handler!(wp::handlers::wrap::level(stderr, wp::LogLevel::ERROR));
+///
+///     info!("Goes to stdout only");
+///     error!("Goes to both stdout and stderr");
+/// }
+///
+/// ```
+pub fn level(handler: Box<dyn Handler>, level: LogLevel) -> Box<dyn Handler> {
+    Box::new(Level {
+        handler,
+        level,
+    })
+}
+
+struct Reformat {
+    handler: Box<dyn Handler>,
+    formatter: Arc<Formatter>,
+}
+
+impl Handler for Reformat {
+    fn emit(&self, record: &dyn Record) {
+        self.handler.emit(&Amended::new(record, record.level(), record.msg(), &self.formatter));
+    }
+
+    fn flush(&self) {
+        self.handler.flush();
+    }
+
+    fn close(&self) {
+        self.handler.close();
+    }
+}
+
+/// Pushes into the handler the log records formatted by the given formatter
+/// instead of the global one.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     let formatter = wp::formatters::pattern::formatter("{level}: {msg}").unwrap();
+///     wp_register_handler!(wp::handlers::wrap::formatter(wp::handlers::stderr::handler(), formatter));
+///
+///     warn!("It's alive!");
+/// }
+///
+/// ```
+pub fn formatter(handler: Box<dyn Handler>, formatter: Formatter) -> Box<dyn Handler> {
+    Box::new(Reformat {
+        handler,
+        formatter: Arc::new(formatter),
+    })
+}
+
+// Routing of the modules to the handlers defined by the spec.
+//
+// The handlers are referred to by the position in the spec
+// which is also the position in the logger.
+// The handlers registered afterwards aren't routed.
+//
+// The routes are matched against the path of the callsite, i.e. `module@file`,
+// the same way as the log rules.
+pub(crate) struct Routes {
+    routes: Vec<(Option<PathPattern>, String, Vec<usize>)>,
+    handlers: usize,
+}
+
+impl Routes {
+    pub(crate) fn new() -> Self {
+        Routes {
+            routes: Vec::new(),
+            handlers: 0,
+        }
+    }
+
+    pub(crate) fn build(spec: &spec::Root) -> Result<Self, String> {
+        let mut routes = Routes::new();
+        routes.handlers = spec.handlers.len();
+        for module in &spec.modules {
+            if !module.handlers.is_empty() {
+                let handlers = module.handlers.iter()
+                    .filter_map(|name| spec.handlers.iter().position(|hspec| &hspec.name == name))
+                    .collect::<Vec<_>>();
+                routes.add(&module.path, handlers)?;
+            }
+        }
+        Ok(routes)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    fn add(&mut self, path: &str, handlers: Vec<usize>) -> Result<(), String> {
+        let pattern = if path_pattern::is_pattern(path) {
+            Some(PathPattern::new(path)?)
+        } else {
+            None
+        };
+        self.routes.push((pattern, path.to_string(), handlers));
+        Ok(())
+    }
+
+    // Returns the index of the route that matches the longer part of the path.
+    pub(crate) fn lookup(&self, path: &str) -> Option<usize> {
+        let mut found: Option<(usize, usize)> = None;
+        for (idx, (pattern, rpath, _)) in self.routes.iter().enumerate() {
+            let len = match pattern {
+                Some(pattern) => pattern.matches(path),
+                None if logger::is_parent(rpath, path) => Some(rpath.len()),
+                None => None,
+            };
+            if let Some(len) = len {
+                if found.is_none_or(|(flen, _)| len >= flen) {
+                    found = Some((len, idx));
+                }
+            }
+        }
+        found.map(|(_, idx)| idx)
+    }
+
+    // Returns the route found with `lookup`.
+    #[inline(always)]
+    pub(crate) fn get(&self, idx: Option<usize>) -> Route<'_> {
+        Route {
+            handlers: idx.map(|idx| self.routes[idx].2.as_slice()),
+            routed: self.handlers,
+        }
+    }
+}
+
+// The handlers the record of a module is routed to.
+pub(crate) struct Route<'a> {
+    handlers: Option<&'a [usize]>,
+    routed: usize,
+}
+
+impl<'a> Route<'a> {
+    // Checks whether the record goes into the handler at the given position.
+    #[inline(always)]
+    pub(crate) fn allows(&self, idx: usize) -> bool {
+        idx >= self.routed || self.handlers.is_none_or(|handlers| handlers.contains(&idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    use record::imp::{RecordMeta, RecordContext, SyncRecord};
+
+    static INFO: RecordMeta = RecordMeta::new(LogLevel::INFO, "foo::bar", "foo/bar.rs", "foo::bar@foo/bar.rs", 42);
+
+    static ERROR: RecordMeta = RecordMeta::new(LogLevel::ERROR, "foo::qux", "foo/qux.rs", "foo::qux@foo/qux.rs", 42);
+
+    fn sink() -> (Box<dyn Handler>, Arc<Mutex<Vec<String>>>) {
+        let out = Arc::new(Mutex::new(Vec::new()));
+        let handler = {
+            let out = out.clone();
+            Box::new(move |record: &dyn Record| {
+                out.lock().unwrap().push(record.formatted().to_string());
+            })
+        };
+        (handler, out)
+    }
+
+    fn push(handler: &dyn Handler, record: &'static RecordMeta) {
+        let formatter: Arc<Formatter> = Arc::new(Box::new(|record| record.msg().to_string()));
+        handler.emit(&SyncRecord::new(record, RecordContext::new(0, 0), format_args!("{}", record.module), formatter));
+    }
+
+    #[test]
+    fn test_wrap_level() {
+        let (handler, out) = sink();
+        let handler = level(handler, LogLevel::WARN);
+        push(handler.as_ref(), &INFO);
+        push(handler.as_ref(), &ERROR);
+        assert_eq!(*out.lock().unwrap(), vec!["foo::qux"]);
+    }
+
+    #[test]
+    fn test_wrap_formatter() {
+        let (handler, out) = sink();
+        let handler = formatter(handler, Box::new(|record| format!("{}:{}", record.level(), record.msg())));
+        push(handler.as_ref(), &INFO);
+        assert_eq!(*out.lock().unwrap(), vec!["INFO:foo::bar"]);
+    }
+
+    #[test]
+    fn test_wrap_route() {
+        let spec = spec::parse(r#"{
+            "handlers": [
+                {"name": "a", "type": "stdout"},
+                {"name": "b", "type": "stdout"},
+                {"name": "c", "type": "stdout"}
+            ],
+            "modules": [
+                {"path": "foo", "handlers": ["a"]},
+                {"path": "foo::qux", "handlers": ["b"]},
+                {"path": "*::xyz", "handlers": ["a", "b"]},
+                {"path": "bar@src/db.rs", "handlers": ["c"]}
+            ]
+        }"#).unwrap();
+        let routes = Routes::build(&spec).unwrap();
+        let allows = |path: &str| -> Vec<usize> {
+            let route = routes.get(routes.lookup(path));
+            (0..4).filter(|&idx| route.allows(idx)).collect()
+        };
+
+        assert_eq!(allows("foo::bar@foo/bar.rs"), vec![0, 3]);
+        assert_eq!(allows("foo::qux::baz@foo/qux/baz.rs"), vec![1, 3]);
+        assert_eq!(allows("bar::xyz@bar/xyz.rs"), vec![0, 1, 3]);
+        assert_eq!(allows("bar@src/lib.rs"), vec![0, 1, 2, 3]);
+        assert_eq!(allows("bar@src/db.rs"), vec![2, 3]);
+        assert_eq!(allows("foobar@foobar.rs"), vec![0, 1, 2, 3]);
+        assert_eq!(allows("foo@foo.rs"), vec![0, 3]);
+        assert!(Routes::new().is_empty());
+    }
+}
@@ -12,12 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+extern crate parking_lot;
+use self::parking_lot::RwLock;
+
 use std::fmt;
+use std::cmp::Ordering;
 use std::str::FromStr;
 use std::collections::HashMap;
 
 /// The logging levels.
-#[derive(PartialEq, PartialOrd, Ord, Eq, Clone, Copy, Debug)]
+///
+/// Levels are compared by their numeric values, see the [register](fn.register.html)
+/// function for the user-defined levels.
+#[derive(Clone, Copy, Debug)]
 pub enum LogLevel {
     #[doc(hidden)]
     UNSUPPORTED,
@@ -39,11 +46,44 @@ pub enum LogLevel {
     CRITICAL,
     #[doc(hidden)]
     LOG,
+    /// Log nothing, not even the records of the [log](../macro.log.html) macro without a level.
+    ///
+    /// Not supposed to be used as the level of a log record.
+    OFF,
+    /// User-defined level at the given numeric position.
+    ///
+    /// See the [register](fn.register.html) function.
+    CUSTOM(isize),
 }
 
-impl From<LogLevel> for isize {
-    fn from(orig: LogLevel) -> isize {
-        match orig {
+impl PartialEq for LogLevel {
+    #[inline(always)]
+    fn eq(&self, other: &LogLevel) -> bool {
+        isize::from(*self) == isize::from(*other)
+    }
+}
+
+impl Eq for LogLevel {}
+
+impl PartialOrd for LogLevel {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &LogLevel) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LogLevel {
+    #[inline(always)]
+    fn cmp(&self, other: &LogLevel) -> Ordering {
+        isize::from(*self).cmp(&isize::from(*other))
+    }
+}
+
+impl LogLevel {
+    #[doc(hidden)]
+    #[inline(always)]
+    pub const fn value(self) -> isize {
+        match self {
             LogLevel::UNSUPPORTED => -10000,
             LogLevel::TRACE => -30,
             LogLevel::DEBUG => -20,
@@ -54,8 +94,89 @@ impl From<LogLevel> for isize {
             LogLevel::ERROR => 30,
             LogLevel::CRITICAL => 40,
             LogLevel::LOG => 50,
+            LogLevel::OFF => 60,
+            LogLevel::CUSTOM(value) => value,
         }
     }
+
+    // Unlike `From<isize>` doesn't consult the registry of the custom levels,
+    // so that the unknown values are kept as is.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub const fn from_value(value: isize) -> LogLevel {
+        match value {
+            -10000 => LogLevel::UNSUPPORTED,
+            -30 => LogLevel::TRACE,
+            -20 => LogLevel::DEBUG,
+            -10 => LogLevel::VERBOSE,
+            0   => LogLevel::INFO,
+            10  => LogLevel::NOTICE,
+            20  => LogLevel::WARN,
+            30  => LogLevel::ERROR,
+            40  => LogLevel::CRITICAL,
+            50  => LogLevel::LOG,
+            60  => LogLevel::OFF,
+            _   => LogLevel::CUSTOM(value),
+        }
+    }
+}
+
+impl From<LogLevel> for isize {
+    #[inline(always)]
+    fn from(orig: LogLevel) -> isize {
+        orig.value()
+    }
+}
+
+/// The most verbose log level compiled in.
+///
+/// It's defined by the cargo features `max_level_<level>` and,
+/// for the builds without debug assertions, `release_max_level_<level>`,
+/// e.g. `max_level_info` or `release_max_level_warn`.
+/// The `<level>` is one of `trace`, `debug`, `verbose`, `info`, `notice`,
+/// `warn`, `error`, `critical` or `off`.
+/// If several features are enabled the most restrictive one wins.
+///
+/// The log records and the conditional code below this level compile to nothing.
+///
+/// It's `None` if no such feature is enabled.
+pub const STATIC_MAX_LEVEL: Option<LogLevel> = static_max_level();
+
+const fn static_max_level() -> Option<LogLevel> {
+    let release = !cfg!(debug_assertions);
+    if cfg!(feature = "max_level_off") || (release && cfg!(feature = "release_max_level_off")) {
+        Some(LogLevel::OFF)
+    } else if cfg!(feature = "max_level_critical") || (release && cfg!(feature = "release_max_level_critical")) {
+        Some(LogLevel::CRITICAL)
+    } else if cfg!(feature = "max_level_error") || (release && cfg!(feature = "release_max_level_error")) {
+        Some(LogLevel::ERROR)
+    } else if cfg!(feature = "max_level_warn") || (release && cfg!(feature = "release_max_level_warn")) {
+        Some(LogLevel::WARN)
+    } else if cfg!(feature = "max_level_notice") || (release && cfg!(feature = "release_max_level_notice")) {
+        Some(LogLevel::NOTICE)
+    } else if cfg!(feature = "max_level_info") || (release && cfg!(feature = "release_max_level_info")) {
+        Some(LogLevel::INFO)
+    } else if cfg!(feature = "max_level_verbose") || (release && cfg!(feature = "release_max_level_verbose")) {
+        Some(LogLevel::VERBOSE)
+    } else if cfg!(feature = "max_level_debug") || (release && cfg!(feature = "release_max_level_debug")) {
+        Some(LogLevel::DEBUG)
+    } else if cfg!(feature = "max_level_trace") || (release && cfg!(feature = "release_max_level_trace")) {
+        Some(LogLevel::TRACE)
+    } else {
+        None
+    }
+}
+
+/// Checks whether the log level is compiled in.
+///
+/// See [STATIC_MAX_LEVEL](constant.STATIC_MAX_LEVEL.html).
+#[doc(hidden)]
+#[inline(always)]
+pub const fn static_enabled(level: LogLevel) -> bool {
+    match STATIC_MAX_LEVEL {
+        Some(max) => level.value() >= max.value(),
+        None => true,
+    }
 }
 
 impl From<isize> for LogLevel {
@@ -71,7 +192,14 @@ impl From<isize> for LogLevel {
             30  => LogLevel::ERROR,
             40  => LogLevel::CRITICAL,
             50  => LogLevel::LOG,
-            _   => LogLevel::UNSUPPORTED,
+            60  => LogLevel::OFF,
+            _   => {
+                if CUSTOM.read().names.contains_key(&orig) {
+                    LogLevel::CUSTOM(orig)
+                } else {
+                    LogLevel::UNSUPPORTED
+                }
+            }
         }
     }
 }
@@ -94,16 +222,85 @@ lazy_static! {
         for level in &LEVELS {
             levels.insert(level.to_string().to_uppercase(), *level);
         }
+        levels.insert(LogLevel::OFF.to_string(), LogLevel::OFF);
+        levels.insert("ALL".to_string(), LogLevel::TRACE);
         levels
     };
+
+    static ref CUSTOM: RwLock<Custom> = RwLock::new(Custom::default());
+}
+
+#[derive(Default)]
+struct Custom {
+    levels: HashMap<String, isize>,
+    names: HashMap<isize, String>,
+}
+
+/// Registers a user-defined log level.
+///
+/// The `name` is case-insensitive and may consist of alphanumeric characters and `_`.
+/// The `value` positions the level among the built-in ones:
+/// `TRACE` is `-30`, `DEBUG` is `-20`, `VERBOSE` is `-10`, `INFO` is `0`,
+/// `NOTICE` is `10`, `WARN` is `20`, `ERROR` is `30` and `CRITICAL` is `40`.
+/// The value must be in the range `(-10000, 50)` and must not clash with a built-in level.
+///
+/// Registered levels can be used in the log specs, are printed by name
+/// in the formatters and can be given to the [log](../macro.log.html) macro.
+/// See the [wp_custom_level](../macro.wp_custom_level.html) macro
+/// to generate the dedicated logging macro.
+///
+/// Registering the same name with the same value again is not an error.
+///
+/// # Example
+///
+/// ```rust
+/// use woodpecker as wp;
+///
+/// let audit = wp::levels::register("audit", 35).unwrap();
+/// assert!(audit > wp::LogLevel::ERROR && audit < wp::LogLevel::CRITICAL);
+/// assert_eq!("AUDIT".parse(), Ok(audit));
+/// assert_eq!(audit.to_string(), "AUDIT");
+///
+/// assert!(wp::levels::register("warning", 20).is_err());
+/// ```
+pub fn register(name: &str, value: isize) -> Result<LogLevel, String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Invalid level name '{}'", name));
+    }
+    if value <= isize::from(LogLevel::UNSUPPORTED) || value >= isize::from(LogLevel::LOG) {
+        return Err(format!("Level value {} is out of range", value));
+    }
+
+    let name = name.to_uppercase();
+    if LMAP.contains_key(&name) {
+        return Err(format!("Level '{}' is built-in", name));
+    }
+    match LogLevel::from(value) {
+        LogLevel::UNSUPPORTED | LogLevel::CUSTOM(_) => {},
+        builtin => return Err(format!("Level value {} is taken by {}", value, builtin)),
+    }
+
+    let mut custom = CUSTOM.write();
+    match (custom.levels.get(&name), custom.names.get(&value)) {
+        (Some(v), _) if *v == value => return Ok(LogLevel::CUSTOM(value)),
+        (Some(v), _) => return Err(format!("Level '{}' is registered as {}", name, v)),
+        (None, Some(other)) => return Err(format!("Level value {} is taken by {}", value, other)),
+        (None, None) => {},
+    }
+    custom.levels.insert(name.clone(), value);
+    custom.names.insert(value, name);
+    Ok(LogLevel::CUSTOM(value))
 }
 
 impl FromStr for LogLevel {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some(level) = LMAP.get(&s.to_uppercase()) {
+        let s = s.to_uppercase();
+        if let Some(level) = LMAP.get(&s) {
             Ok(*level)
+        } else if let Some(value) = CUSTOM.read().levels.get(&s) {
+            Ok(LogLevel::CUSTOM(*value))
         } else {
             Err(())
         }
@@ -124,6 +321,13 @@ impl fmt::Display for LogLevel {
             LogLevel::ERROR => write!(f, "ERROR"),
             LogLevel::CRITICAL => write!(f, "CRITICAL"),
             LogLevel::LOG => write!(f, "LOG"),
+            LogLevel::OFF => write!(f, "OFF"),
+            LogLevel::CUSTOM(value) => {
+                match CUSTOM.read().names.get(&value) {
+                    Some(name) => write!(f, "{}", name),
+                    None => write!(f, "{}", value),
+                }
+            }
         }
     }
 }
@@ -152,4 +356,67 @@ mod tests {
         assert_eq!(Ordering::Greater, LogLevel::WARN.cmp(&LogLevel::INFO));
         assert!(LogLevel::WARN >= LogLevel::INFO);
     }
+
+    #[test]
+    fn test_logger_level_off() {
+        assert_eq!("off".parse(), Ok(LogLevel::OFF));
+        assert_eq!("ALL".parse(), Ok(LogLevel::TRACE));
+        assert_eq!("log".parse::<LogLevel>(), Err(()));
+        assert_eq!(LogLevel::OFF.to_string(), "OFF");
+
+        let level: isize = LogLevel::OFF.into();
+        assert_eq!(LogLevel::from(level), LogLevel::OFF);
+
+        assert!(LogLevel::OFF > LogLevel::LOG);
+        assert!(LogLevel::OFF > LogLevel::CRITICAL);
+    }
+
+    #[test]
+    fn test_logger_level_custom() {
+        let audit = register("audit", 35).unwrap();
+        assert_eq!(audit, LogLevel::CUSTOM(35));
+        assert_eq!(register("Audit", 35), Ok(audit));
+        assert!(register("audit", 36).is_err());
+        assert!(register("audit2", 35).is_err());
+
+        let spam = register("spam", -40).unwrap();
+        assert!(spam < LogLevel::TRACE);
+        assert!(audit > LogLevel::ERROR);
+        assert!(audit < LogLevel::CRITICAL);
+        assert_eq!(Ordering::Equal, LogLevel::CUSTOM(20).cmp(&LogLevel::WARN));
+
+        assert_eq!("audit".parse(), Ok(audit));
+        assert_eq!("SPAM".parse(), Ok(spam));
+        assert_eq!(audit.to_string(), "AUDIT");
+        assert_eq!(LogLevel::CUSTOM(37).to_string(), "37");
+
+        let level: isize = audit.into();
+        assert_eq!(LogLevel::from(level), audit);
+        assert_eq!(LogLevel::from(37), LogLevel::UNSUPPORTED);
+        assert_eq!(LogLevel::from_value(37), LogLevel::CUSTOM(37));
+        assert_eq!(LogLevel::from_value(level), audit);
+        assert_eq!(LogLevel::from_value(-30), LogLevel::TRACE);
+
+        assert!(register("", 36).is_err());
+        assert!(register("a=b", 36).is_err());
+        assert!(register("warn", 36).is_err());
+        assert!(register("foo", 20).is_err());
+        assert!(register("foo", 50).is_err());
+        assert!(register("foo", -10000).is_err());
+    }
+
+    #[test]
+    fn test_logger_level_static() {
+        assert!(static_enabled(LogLevel::CRITICAL));
+        match STATIC_MAX_LEVEL {
+            Some(max) => {
+                assert!(static_enabled(max));
+                assert!(max == LogLevel::TRACE || !static_enabled(LogLevel::TRACE));
+            },
+            None => {
+                assert!(static_enabled(LogLevel::TRACE));
+                assert!(static_enabled(LogLevel::CUSTOM(-40)));
+            },
+        }
+    }
 }
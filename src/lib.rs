@@ -69,6 +69,9 @@
 //! It's important to use [sync](fn.sync.html) in the end of the `main` funtion
 //! to ensure that all log records are properly flushed.
 //!
+//! The [shutdown](fn.shutdown.html) function stops the log thread and closes the log handlers.
+//! The logging might be initialized again afterwards.
+//!
 //! The logging thread could be activated via a configuration parameter passed
 //! to the [wp_init](macro.wp_init!.html) macro.
 //! The `WP_LOG_THREAD` environment variable may be used overrides compile-time settings.
@@ -99,7 +102,7 @@ pub use record::Record;
 #[macro_use]
 pub mod logger;
 #[doc(inline)]
-pub use logger::{init, sync};
+pub use logger::{init, sync, shutdown, configure, Logger};
 
 #[doc(hidden)]
 pub mod line_range;
@@ -107,14 +110,31 @@ pub mod line_range;
 pub use line_range::LineRangeBound;
 pub use line_range::LineRangeBound::{BOF, EOF};
 
+#[doc(hidden)]
+pub mod path_pattern;
+
 /// Collection of log handlers.
 pub mod handlers;
 
 /// Collection of log record formatters.
 pub mod formatters;
 
+pub mod filters;
+
+pub mod redact;
+
+pub mod stats;
+#[doc(inline)]
+pub use stats::stats;
+
 #[doc(hidden)]
 pub mod global;
 
-#[doc(inline)]
+pub mod testing;
+
+pub mod meta;
+
+/// Logging spec definition.
 pub mod spec;
+
+pub mod loader;
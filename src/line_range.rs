@@ -12,8 +12,6 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std;
-
 use levels::LogLevel;
 
 /// The markers of the begging of the file and its end.
@@ -29,7 +27,7 @@ impl From<LineRangeBound> for u32 {
     fn from(orig: LineRangeBound) -> u32 {
         match orig {
             LineRangeBound::BOF => 0,
-            LineRangeBound::EOF => std::u32::MAX,
+            LineRangeBound::EOF => u32::MAX,
         }
     }
 }
@@ -53,8 +51,8 @@ impl Range {
             Err(RangeError::InvalidRange(from, to))
         } else {
             Ok(Range {
-                from: from,
-                to: to,
+                from,
+                to,
             })
         }
     }
@@ -86,8 +84,8 @@ impl LineRangeSpec {
     #[inline(always)]
     fn new(level: LogLevel, range: Range) -> Self {
         LineRangeSpec {
-            level: level,
-            range: range,
+            level,
+            range,
         }
     }
 
@@ -509,7 +507,7 @@ mod tests {
         assert_eq!(expected, output);
 
         // invalid range
-        let output = from_vtuple(&vec![(15, 30), (10, 20), (20, 10)]);
+        let output = from_vtuple(&[(15, 30), (10, 20), (20, 10)]);
         let expected = Err(RangeError::InvalidRange(20, 10));
         assert_eq!(expected, output);
 
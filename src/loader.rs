@@ -0,0 +1,333 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Layered loading of the logging configuration.
+//!
+//! The [Loader](struct.Loader.html) merges the log [specs](../spec/index.html)
+//! from several sources.
+//! The sources are expected to be added in the order of precedence:
+//!
+//! * built-in defaults;
+//! * configuration file;
+//! * `RUST_LOG` environment variable;
+//! * programmatic overrides.
+//!
+//! The global log level is taken from the last source that defines it.
+//!
+//! A module rule overrides the rules of the previous sources defined for the same path.
+//! A rule for the ranges of lines overrides only the rules for the intersecting ranges.
+//! The rules for different paths are merged.
+//!
+//! The handlers and formatters are taken from the last source that defines any of them.
+//!
+//! The loader keeps track of the source of each rule.
+//!
+//! # Example
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate woodpecker;
+//! use woodpecker as wp;
+//! use wp::loader::{Loader, Source};
+//!
+//! fn main() {
+//!     wp_init!();
+//!
+//!     let mut loader = Loader::new();
+//!     loader.defaults("info,foo::db=error").unwrap()
+//!         .env().unwrap()
+//!         .set("foo::db=debug").unwrap();
+//!     loader.apply().unwrap();
+//!
+//!     assert_eq!(wp_get_level!("foo::db"), wp::LogLevel::DEBUG);
+//!     for rule in loader.rules() {
+//!         println!("{:?}={} set by {}", rule.path, rule.level, rule.source);
+//!     }
+//! }
+//!
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::env;
+
+use levels::LogLevel;
+use logger;
+use spec::{self, Filter, Module, Root};
+
+/// The source of the log spec.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Source {
+    /// Built-in defaults.
+    Defaults,
+    /// Configuration file.
+    File(PathBuf),
+    /// The `RUST_LOG` environment variable.
+    Env,
+    /// Programmatic override.
+    Override,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::Defaults => write!(f, "defaults"),
+            Source::File(ref path) => write!(f, "{}", path.display()),
+            Source::Env => write!(f, "RUST_LOG"),
+            Source::Override => write!(f, "override"),
+        }
+    }
+}
+
+/// A log rule along with its source.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Rule {
+    /// Path of the module or file, `None` for the global log level.
+    pub path: Option<String>,
+    /// Log level.
+    pub level: LogLevel,
+    /// Ranges of lines, empty if the rule applies to the whole file.
+    pub lines: Vec<(u32, u32)>,
+    /// The source that defined the rule.
+    pub source: Source,
+}
+
+/// Merges the log specs from several sources.
+///
+/// See documentation of the [loader](index.html) module for the details.
+#[derive(Default, Clone)]
+pub struct Loader {
+    level: Option<(LogLevel, Source)>,
+    modules: Vec<(Module, Source)>,
+    filter: Option<Filter>,
+    root: Option<Root>,
+}
+
+fn overrides(new: &Module, old: &Module) -> bool {
+    if new.path != old.path || new.lranges.is_empty() != old.lranges.is_empty() {
+        return false;
+    }
+    new.lranges.is_empty()
+        || new.lranges.iter().any(|nrange| old.lranges.iter().any(|orange| nrange.intersects(orange)))
+}
+
+impl Loader {
+    /// Creates an empty loader.
+    pub fn new() -> Self {
+        Loader::default()
+    }
+
+    /// Merges the log spec from the given source.
+    ///
+    /// The loader is left intact if the merged spec is inconsistent.
+    pub fn merge(&mut self, spec: Root, source: Source) -> Result<&mut Self, String> {
+        let mut merged = self.clone();
+        if let Some(level) = spec.level {
+            merged.level = Some((level, source.clone()));
+        }
+        if spec.filter.is_some() {
+            merged.filter = spec.filter.clone();
+        }
+
+        if !spec.handlers.is_empty() || !spec.formatters.is_empty() || spec.formatter.is_some() {
+            // Drop the routes to the handlers that are no longer defined.
+            for (module, _) in &mut merged.modules {
+                module.handlers.retain(|name| spec.handlers.iter().any(|h| &h.name == name));
+            }
+            merged.root = Some(Root {
+                modules: Vec::new(),
+                ..spec.clone()
+            });
+        }
+
+        for module in spec.modules {
+            merged.modules.retain(|(old, _)| !overrides(&module, old));
+            merged.modules.push((module, source.clone()));
+        }
+
+        // Make sure the merged spec is consistent.
+        spec::squash(merged.spec()).map_err(|err| err.to_string())?;
+        *self = merged;
+        Ok(self)
+    }
+
+    /// Merges the built-in default log spec.
+    pub fn defaults(&mut self, spec: &str) -> Result<&mut Self, String> {
+        let spec = spec::parse(spec).map_err(|err| err.to_string())?;
+        self.merge(spec, Source::Defaults)
+    }
+
+    /// Merges the log spec from the file.
+    ///
+    /// The file is parsed as TOML if it has the `.toml` extension.
+    /// Otherwise it's either JSON or env_logger spec.
+    pub fn file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self, String> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        let spec = match path.extension() {
+            Some(ext) if ext == "toml" => spec::parse_toml(&content),
+            _ => spec::parse(&content),
+        };
+        let spec = spec.map_err(|err| format!("{}: {}", path.display(), err))?;
+        self.merge(spec, Source::File(path.to_path_buf()))
+    }
+
+    /// Merges the log spec from the `RUST_LOG` environment variable if it's set.
+    pub fn env(&mut self) -> Result<&mut Self, String> {
+        match env::var("RUST_LOG") {
+            Ok(ref rust_log) => {
+                let spec = spec::parse(rust_log).map_err(|err| format!("RUST_LOG: {}", err))?;
+                self.merge(spec, Source::Env)
+            },
+            Err(_) => Ok(self),
+        }
+    }
+
+    /// Merges the programmatic override of the log spec.
+    pub fn set(&mut self, spec: &str) -> Result<&mut Self, String> {
+        let spec = spec::parse(spec).map_err(|err| err.to_string())?;
+        self.merge(spec, Source::Override)
+    }
+
+    /// Returns the merged log rules along with their sources.
+    ///
+    /// The global log level goes first if it's defined.
+    pub fn rules(&self) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        if let Some((level, ref source)) = self.level {
+            rules.push(Rule {
+                path: None,
+                level,
+                lines: Vec::new(),
+                source: source.clone(),
+            });
+        }
+        for (module, source) in &self.modules {
+            rules.push(Rule {
+                path: Some(module.path.clone()),
+                level: module.level,
+                lines: module.lranges.iter().map(|range| (range.from, range.to)).collect(),
+                source: source.clone(),
+            });
+        }
+        rules
+    }
+
+    #[doc(hidden)]
+    pub fn spec(&self) -> Root {
+        let mut root = match self.root {
+            Some(ref root) => root.clone(),
+            None => Root::new(),
+        };
+        root.level = self.level.as_ref().map(|&(level, _)| level);
+        root.filter = self.filter.clone();
+        root.modules = self.modules.iter().map(|(module, _)| module.clone()).collect();
+        root
+    }
+
+    /// Applies the merged log spec to the global logger.
+    ///
+    /// The log rules are replaced.
+    /// The handlers and formatter are replaced only if any source defines them.
+    ///
+    /// The global log level is set to `WARN` unless defined by any source.
+    pub fn apply(&self) -> Result<(), String> {
+        let spec = spec::squash(self.spec()).map_err(|err| err.to_string())?;
+        logger::apply(&spec, self.root.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+    use self::tempdir::TempDir;
+
+    use super::*;
+
+    use std::io::Write;
+
+    fn rule(path: Option<&str>, level: LogLevel, lines: &[(u32, u32)], source: Source) -> Rule {
+        Rule {
+            path: path.map(str::to_string),
+            level,
+            lines: lines.to_vec(),
+            source,
+        }
+    }
+
+    #[test]
+    fn test_loader() {
+        let dir = TempDir::new("wp-loader").unwrap();
+        let path = dir.path().join("log.toml");
+        fs::File::create(&path).unwrap().write_all(br#"
+            level = "info"
+
+            [[modules]]
+            path = "foo::bar"
+            level = "critical"
+
+            [[modules]]
+            path = "qux"
+            level = "debug"
+            lines = [[10, 20]]
+        "#).unwrap();
+
+        let mut loader = Loader::new();
+        loader.defaults("error,foo=debug,foo::bar=warn").unwrap()
+            .file(&path).unwrap()
+            .set(r#"{"modules": [{"path": "qux", "level": "trace", "lines": [[15, 30]]},
+                                 {"path": "qux", "level": "error"}]}"#).unwrap();
+
+        let file = Source::File(path.clone());
+        assert_eq!(loader.rules(), vec![
+            rule(None, LogLevel::INFO, &[], file.clone()),
+            rule(Some("foo"), LogLevel::DEBUG, &[], Source::Defaults),
+            rule(Some("foo::bar"), LogLevel::CRITICAL, &[], file.clone()),
+            rule(Some("qux"), LogLevel::ERROR, &[], Source::Override),
+            rule(Some("qux"), LogLevel::TRACE, &[(15, 30)], Source::Override),
+        ]);
+        assert_eq!(format!("{}", file), path.display().to_string());
+        assert_eq!(format!("{}", Source::Env), "RUST_LOG");
+
+        let spec = loader.spec();
+        assert_eq!(spec.level, Some(LogLevel::INFO));
+        assert_eq!(spec.modules.len(), 4);
+        assert!(spec.handlers.is_empty());
+        assert!(spec.filter.is_none());
+
+        loader.set("/!heartbeat").unwrap().set("warn").unwrap();
+        assert_eq!(loader.spec().filter.unwrap().to_string(), "!heartbeat");
+
+        assert!(loader.file(dir.path().join("none.toml")).is_err());
+        assert!(loader.set("foo=bar").is_err());
+        assert_eq!(loader.rules().len(), 5);
+    }
+
+    #[test]
+    fn test_loader_handlers() {
+        let mut loader = Loader::new();
+        loader.defaults(r#"{"handlers": [{"name": "a", "type": "stdout"}],
+                            "modules": [{"path": "foo", "handlers": ["a"]}]}"#).unwrap();
+        assert_eq!(loader.spec().modules[0].handlers, vec!["a".to_string()]);
+
+        loader.set(r#"{"handlers": [{"name": "b", "type": "stderr"}]}"#).unwrap();
+        let spec = loader.spec();
+        assert_eq!(spec.handlers.len(), 1);
+        assert_eq!(spec.handlers[0].name, "b");
+        assert!(spec.modules[0].handlers.is_empty());
+    }
+}
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 extern crate parking_lot;
-use self::parking_lot::RwLock;
+use self::parking_lot::{RwLock, RwLockReadGuard};
 
 extern crate time;
 
@@ -24,10 +24,9 @@ use self::crossbeam::mem::CachePadded;
 extern crate thread_id;
 
 use std::ops::Deref;
-use std::mem;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering,
-                        ATOMIC_USIZE_INIT, ATOMIC_BOOL_INIT};
+use std::array;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, AtomicU32, AtomicU64, AtomicBool, Ordering};
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::collections::Bound::{Included, Excluded, Unbounded};
@@ -35,90 +34,342 @@ use std::time::{Duration, Instant};
 use std::thread;
 use std::fmt;
 use std::env;
+use std::path::Path;
 
 use levels::LogLevel;
 use record::Record;
-use record::imp::{SyncRecord, AsyncRecord, RecordMeta};
+use record::imp;
+use record::imp::{Amended, SyncRecord, AsyncRecord, RecordMeta, RecordContext};
 use line_range;
 use line_range::LineRangeSpec;
-use formatters::Formatter;
-use handlers::Handler;
+use path_pattern::{self, PathPattern};
+use formatters::{self, Formatter};
+use formatters::{pattern, timestamp};
+use filters::{self, Filter};
+use redact;
+use stats;
+use handlers::{self, Handler};
+use handlers::wrap::{self, Route, Routes};
+use spec::{self, HandlerKind};
 use config::Config;
 use global;
+use meta;
+use testing;
 
 const QNUM: usize = 64;
 
-static LOG_THREAD: AtomicBool = ATOMIC_BOOL_INIT;
-static IS_INIT: AtomicBool = ATOMIC_BOOL_INIT;
-static RGEN: AtomicUsize = ATOMIC_USIZE_INIT;
+static LOG_THREAD: AtomicBool = AtomicBool::new(false);
+static ORDERED: AtomicBool = AtomicBool::new(false);
+static SEQ: AtomicU64 = AtomicU64::new(0);
+static PREFIX_MATCH: AtomicBool = AtomicBool::new(false);
+static IS_INIT: AtomicBool = AtomicBool::new(false);
+static STOP: AtomicBool = AtomicBool::new(false);
+static RGEN: AtomicUsize = AtomicUsize::new(0);
+static PGEN: AtomicU32 = AtomicU32::new(1);
+static LTHREAD: Mutex<Option<thread::JoinHandle<()>>> = Mutex::new(None);
 lazy_static! {
     static ref SENT: [CachePadded<AtomicUsize>; QNUM] = {
-        let mut sent: [CachePadded<AtomicUsize>; QNUM] = unsafe { mem::uninitialized() };
-        for sent in sent.iter_mut() {
-            let z = mem::replace(sent, CachePadded::new(ATOMIC_USIZE_INIT));
-            mem::forget(z);
-        }
-        sent
+        array::from_fn(|_| CachePadded::new(AtomicUsize::new(0)))
     };
-    static ref RECEIVED: CachePadded<AtomicUsize> = CachePadded::new(ATOMIC_USIZE_INIT);
+    static ref RECEIVED: CachePadded<AtomicUsize> = CachePadded::new(AtomicUsize::new(0));
 }
 
 type QVec = [SegQueue<AsyncRecord>; QNUM];
 
+// Every change of the pattern rules gets a new generation.
+// Zero is never used so that an empty cache doesn't match.
+fn next_pgen() -> u32 {
+    loop {
+        let pgen = PGEN.fetch_add(1, Ordering::Relaxed);
+        if pgen != 0 {
+            return pgen;
+        }
+    }
+}
+
+const NO_PATTERN: u64 = 0xffff_ffff;
+
+// The pattern rule matched at a callsite: the generation of the pattern rules
+// in the upper half, the index of the rule and the length of the match in the lower one.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct PatternCache(AtomicU64);
+
+impl PatternCache {
+    pub const fn new() -> Self {
+        PatternCache(AtomicU64::new(0))
+    }
+
+    #[inline(always)]
+    pub(crate) fn get(&self, pgen: u32) -> Option<Option<(usize, usize)>> {
+        let cached = self.0.load(Ordering::Relaxed);
+        if (cached >> 32) as u32 != pgen {
+            return None;
+        }
+        match cached & NO_PATTERN {
+            NO_PATTERN => Some(None),
+            found => Some(Some(((found >> 16) as usize, (found & 0xffff) as usize))),
+        }
+    }
+
+    pub(crate) fn set(&self, pgen: u32, found: Option<(usize, usize)>) {
+        let cached = match found {
+            None => NO_PATTERN,
+            Some((idx, len)) if idx < 0xffff && len <= 0xffff => (idx as u64) << 16 | len as u64,
+            // Too large to be cached.
+            Some(_) => return,
+        };
+        self.0.store(u64::from(pgen) << 32 | cached, Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug, Clone)]
 struct ModuleSpec {
     level: LogLevel,
     lranges: Arc<Vec<LineRangeSpec>>,
+    filter: Option<spec::Filter>,
+}
+
+impl ModuleSpec {
+    #[inline(always)]
+    fn level_at(&self, line: u32) -> LogLevel {
+        for range in self.lranges.deref() {
+            if range.contains(line) {
+                return range.level;
+            }
+        }
+        self.level
+    }
+}
+
+// Checks whether the rule for the `name` path applies to the `path`.
+//
+// Unless the prefix matching is requested the rule applies only
+// if it ends at the `::` boundary of the module path or
+// at the `/` boundary of the file path.
+#[inline(always)]
+pub(crate) fn is_parent(name: &str, path: &str) -> bool {
+    if !path.starts_with(name) {
+        return false;
+    }
+    if PREFIX_MATCH.load(Ordering::Relaxed) {
+        return true;
+    }
+
+    let rest = &path[name.len()..];
+    rest.is_empty()
+        || rest.starts_with("::") || rest.starts_with(wp_separator!()) || rest.starts_with('/')
+        || name.ends_with("::") || name.ends_with(wp_separator!()) || name.ends_with('/')
+}
+
+#[derive(Debug, Clone)]
+struct PatternSpec {
+    pattern: Arc<PathPattern>,
+    spec: ModuleSpec,
 }
 
 #[doc(hidden)]
 pub struct RootLogger {
     loggers: CachePadded<BTreeMap<String, ModuleSpec>>,
-    handlers: CachePadded<Vec<Arc<Handler>>>,
+    patterns: CachePadded<Vec<PatternSpec>>,
+    // The generation of the pattern rules, see `PatternCache`.
+    pgen: u32,
+    handlers: CachePadded<Vec<Arc<dyn Handler>>>,
+    routes: Arc<Routes>,
+    filters: CachePadded<Vec<Arc<dyn Filter>>>,
+    redactions: CachePadded<Vec<redact::Rule>>,
     formatter: CachePadded<Arc<Formatter>>,
-    queue: CachePadded<Arc<QVec>>,
+    queue: CachePadded<Option<Arc<QVec>>>,
+    // The log level of an independent logger.
+    // The log level of the global logger is kept in the `global` module.
+    level: Option<LogLevel>,
+    // The message filters: the global one and whether any rule has its own one.
+    filter: Option<spec::Filter>,
+    mfilters: bool,
 }
 
 impl RootLogger {
     fn new(queue: Arc<QVec>) -> Self {
         RootLogger {
             loggers: CachePadded::new(BTreeMap::new()),
+            patterns: CachePadded::new(Vec::new()),
+            pgen: next_pgen(),
+            formatter: CachePadded::new(Arc::new(Box::new(::formatters::default::formatter))),
+            handlers: CachePadded::new(Vec::new()),
+            routes: Arc::new(Routes::new()),
+            filters: CachePadded::new(Vec::new()),
+            redactions: CachePadded::new(Vec::new()),
+            queue: CachePadded::new(Some(queue)),
+            level: None,
+            filter: None,
+            mfilters: false,
+        }
+    }
+
+    fn independent() -> Self {
+        RootLogger {
+            loggers: CachePadded::new(BTreeMap::new()),
+            patterns: CachePadded::new(Vec::new()),
+            pgen: next_pgen(),
             formatter: CachePadded::new(Arc::new(Box::new(::formatters::default::formatter))),
             handlers: CachePadded::new(Vec::new()),
-            queue: CachePadded::new(queue),
+            routes: Arc::new(Routes::new()),
+            filters: CachePadded::new(Vec::new()),
+            redactions: CachePadded::new(Vec::new()),
+            queue: CachePadded::new(None),
+            level: Some(LogLevel::WARN),
+            filter: None,
+            mfilters: false,
         }
     }
 
     #[doc(hidden)]
     pub fn update(&mut self, right: &RootLogger) {
         self.loggers = CachePadded::new(right.loggers.clone());
+        self.patterns = CachePadded::new(right.patterns.clone());
+        self.pgen = right.pgen;
         self.handlers = CachePadded::new(right.handlers.clone());
+        self.routes = right.routes.clone();
+        self.filters = CachePadded::new(right.filters.clone());
+        self.redactions = CachePadded::new(right.redactions.clone());
         self.formatter = CachePadded::new(right.formatter.clone());
+        self.filter = right.filter.clone();
+        self.mfilters = right.mfilters;
+    }
+
+    #[inline(always)]
+    fn default_level(&self) -> LogLevel {
+        match self.level {
+            Some(level) => level,
+            None => global::get_level(),
+        }
+    }
+
+    // Independent loggers are neither cached per thread
+    // nor affect the fast path of the global logger.
+    fn changed(&self, loggers: bool) {
+        if self.level.is_none() {
+            if loggers {
+                global::set_loggers(true);
+            }
+            RGEN.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     fn reset(&mut self) {
+        self.reset_config();
+        self.filters.clear();
+        self.redactions.clear();
+    }
+
+    // Resets everything but the filters and redaction rules.
+    fn reset_config(&mut self) {
+        for h in self.handlers.deref() {
+            h.close();
+        }
         self.loggers.clear();
+        self.patterns.clear();
+        self.pgen = next_pgen();
         self.formatter = CachePadded::new(Arc::new(Box::new(::formatters::default::formatter)));
         self.handlers.clear();
-        RGEN.fetch_add(1, Ordering::Relaxed);
+        self.routes = Arc::new(Routes::new());
+        self.filter = None;
+        self.mfilters = false;
+        self.changed(false);
     }
 
     #[doc(hidden)]
     pub fn reset_loggers(&mut self) {
         self.loggers.clear();
-        RGEN.fetch_add(1, Ordering::Relaxed);
+        self.patterns.clear();
+        self.pgen = next_pgen();
+        self.filter = None;
+        self.mfilters = false;
+        self.changed(false);
     }
 
     #[doc(hidden)]
-    pub fn handler(&mut self, handler: Handler) {
-        self.handlers.push(Arc::new(handler));
-        RGEN.fetch_add(1, Ordering::Relaxed);
+    pub fn handler(&mut self, handler: Box<dyn Handler>) {
+        self.handlers.push(Arc::from(handler));
+        self.changed(false);
+    }
+
+    #[doc(hidden)]
+    pub fn add_filter(&mut self, filter: Box<dyn Filter>) {
+        self.filters.push(Arc::from(filter));
+        self.changed(false);
+    }
+
+    #[doc(hidden)]
+    pub fn add_redaction(&mut self, rule: redact::Rule) {
+        self.redactions.push(rule);
+        self.changed(false);
     }
 
     #[doc(hidden)]
     pub fn formatter(&mut self, formatter: Formatter) {
         self.formatter = CachePadded::new(Arc::new(formatter));
-        RGEN.fetch_add(1, Ordering::Relaxed);
+        self.changed(false);
+    }
+
+    fn set_modules(&mut self, modules: &[spec::Module]) -> Result<(), String> {
+        for module in modules {
+            let lranges = match line_range::spec(module.level, &module.lranges) {
+                Ok(lranges) => lranges,
+                Err(err) => return Err(format!("{:?}", err)),
+            };
+            if lranges.is_empty() {
+                self.set_level(&module.path, module.level)?;
+            } else {
+                self.set_level_ranges(&module.path, lranges)?;
+            }
+            if let Some(ref filter) = module.filter {
+                self.set_module_filter(&module.path, filter);
+            }
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    pub fn set_filter(&mut self, filter: Option<spec::Filter>) {
+        self.filter = filter;
+        self.changed(false);
+    }
+
+    fn set_module_filter(&mut self, path: &str, filter: &spec::Filter) {
+        let logger = if path_pattern::is_pattern(path) {
+            self.patterns.iter_mut()
+                .find(|spec| spec.pattern.as_str() == path)
+                .map(|spec| &mut spec.spec)
+        } else {
+            self.loggers.get_mut(path)
+        };
+        if let Some(logger) = logger {
+            logger.filter = Some(filter.clone());
+            self.mfilters = true;
+            self.changed(false);
+        }
+    }
+
+    // Configures the log rules and the message filters.
+    #[doc(hidden)]
+    pub fn set_spec(&mut self, spec: &spec::Root) -> Result<(), String> {
+        if spec.filter.is_some() {
+            self.set_filter(spec.filter.clone());
+        }
+        self.set_modules(&spec.modules)
+    }
+
+    // Replaces the log rules, handlers and formatter.
+    fn configure(&mut self, spec: &spec::Root, formatter: Formatter,
+                 handlers: Vec<Box<dyn Handler>>, routes: Routes) -> Result<(), String> {
+        self.reset_config();
+        self.formatter(formatter);
+        for handler in handlers {
+            self.handler(handler);
+        }
+        self.routes = Arc::new(routes);
+        self.set_spec(spec)
     }
 
     fn remove_children(&mut self, path: &str) {
@@ -129,7 +380,9 @@ impl RootLogger {
                 if !name.starts_with(path) {
                     break;
                 }
-                trash.push(name.to_string());
+                if is_parent(path, name) {
+                    trash.push(name.to_string());
+                }
             }
         }
         for item in trash {
@@ -143,20 +396,93 @@ impl RootLogger {
             return Err("Unsupported log level".to_string());
         }
 
-        self.remove_children(path);
         let logger = ModuleSpec {
-            level: level,
+            level,
             lranges: Arc::new(Vec::new()),
+            filter: None,
         };
+        if path_pattern::is_pattern(path) {
+            return self.set_pattern(path, logger);
+        }
+
+        self.remove_children(path);
         self.loggers.insert(path.to_string(), logger);
-        global::set_loggers(true);
-        RGEN.fetch_add(1, Ordering::Relaxed);
+        self.changed(true);
+
+        Ok(())
+    }
+
+    fn set_pattern(&mut self, path: &str, logger: ModuleSpec) -> Result<(), String> {
+        let pattern = PathPattern::new(path)?;
+        self.patterns.retain(|spec| spec.pattern.as_str() != path);
+        self.patterns.push(PatternSpec {
+            pattern: Arc::new(pattern),
+            spec: logger,
+        });
+        self.pgen = next_pgen();
+        self.changed(true);
 
         Ok(())
     }
 
+    fn set_pattern_ranges(&mut self, path: &str, lranges: Vec<LineRangeSpec>) -> Result<(), String> {
+        let logger = match self.patterns.iter().find(|spec| spec.pattern.as_str() == path) {
+            Some(old) => ModuleSpec {
+                level: old.spec.level,
+                lranges: Arc::new(line_range::merge_spec(&old.spec.lranges, &lranges)),
+                filter: old.spec.filter.clone(),
+            },
+            None => ModuleSpec {
+                level: self.default_level(),
+                lranges: Arc::new(lranges),
+                filter: None,
+            },
+        };
+        self.set_pattern(path, logger)
+    }
+
+    // Exports the log rules of the path into the spec.
+    fn export(modules: &mut Vec<spec::Module>, path: &str, logger: &ModuleSpec) {
+        let mut module = spec::Module::with_level(path, logger.level);
+        module.filter = logger.filter.clone();
+        modules.push(module);
+
+        let mut levels: Vec<LogLevel> = logger.lranges.iter().map(|range| range.level).collect();
+        levels.sort();
+        levels.dedup();
+        for level in levels {
+            let lranges = logger.lranges.iter()
+                .filter(|range| range.level == level)
+                .map(|range| range.range)
+                .collect();
+            modules.push(spec::Module::with_lranges(path, level, lranges));
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn spec(&self) -> spec::Root {
+        let mut root = spec::Root::new();
+        root.level = Some(self.default_level());
+        root.filter = self.filter.clone();
+        for (path, logger) in self.loggers.iter() {
+            RootLogger::export(&mut root.modules, path, logger);
+        }
+        for pattern in self.patterns.iter() {
+            RootLogger::export(&mut root.modules, pattern.pattern.as_str(), &pattern.spec);
+        }
+        root
+    }
+
+    #[doc(hidden)]
+    pub fn get_spec(&self) -> String {
+        spec::to_json(&self.spec())
+    }
+
     #[doc(hidden)]
     pub fn set_level_ranges(&mut self, path: &str, lranges: Vec<LineRangeSpec>) -> Result<(), String> {
+        if path_pattern::is_pattern(path) {
+            return self.set_pattern_ranges(path, lranges);
+        }
         if !path.ends_with("<anon>") && !path.ends_with(".rs") {
             return Err("File path not specified".to_string());
         }
@@ -166,97 +492,300 @@ impl RootLogger {
 
         let level = self.get_level_for_module(path);
 
-        let lranges = if let Some(old) = self.loggers.get(path) {
-            line_range::merge_spec(&old.lranges, &lranges)
+        let (lranges, filter) = if let Some(old) = self.loggers.get(path) {
+            (line_range::merge_spec(&old.lranges, &lranges), old.filter.clone())
         } else {
-            lranges
+            (lranges, None)
         };
 
         let logger = ModuleSpec {
-            level: level,
+            level,
             lranges: Arc::new(lranges),
+            filter,
         };
         self.loggers.insert(path.to_string(), logger);
-        global::set_loggers(true);
-        RGEN.fetch_add(1, Ordering::Relaxed);
+        self.changed(true);
 
         Ok(())
     }
 
-    #[doc(hidden)]
-    pub fn get_level_for_module(&self, path: &str) -> LogLevel {
+    fn find_pattern(&self, path: &str, cache: Option<&PatternCache>) -> Option<(usize, usize)> {
+        if let Some(found) = cache.and_then(|cache| cache.get(self.pgen)) {
+            return found;
+        }
+        // The last defined pattern wins
+        let found = self.patterns.iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(idx, spec)| spec.pattern.matches(path).map(|len| (idx, len)))
+            .next();
+        if let Some(cache) = cache {
+            cache.set(self.pgen, found);
+        }
+        found
+    }
+
+    // The rule that matches the longer part of the path wins.
+    #[inline(always)]
+    fn find(&self, path: &str, cache: Option<&PatternCache>) -> Option<&ModuleSpec> {
+        let mut found = None;
         let range = self.loggers.range::<str, _>((Unbounded, Included(path)));
         for (name, logger) in range.rev() {
-            if path.starts_with(name) {
-                return logger.level;
+            if is_parent(name, path) {
+                found = Some((logger, name.len()));
+                break;
             }
         }
 
-        global::get_level()
+        if !self.patterns.is_empty() {
+            if let Some((idx, len)) = self.find_pattern(path, cache) {
+                if found.is_none_or(|(_, plen)| len >= plen) {
+                    return Some(&self.patterns[idx].spec);
+                }
+            }
+        }
+
+        found.map(|(logger, _)| logger)
+    }
+
+    #[doc(hidden)]
+    pub fn get_level_for_module(&self, path: &str) -> LogLevel {
+        match self.find(path, None) {
+            Some(logger) => logger.level,
+            None => self.default_level(),
+        }
     }
 
     #[doc(hidden)]
     #[inline(always)]
     pub fn get_level(&self, path: &str, line: u32) -> LogLevel {
-        let range = self.loggers.range::<str, _>((Unbounded, Included(path)));
-        for (name, logger) in range.rev() {
-            if path.starts_with(name) {
-                for range in logger.lranges.deref() {
-                    if range.contains(line) {
-                        return range.level;
-                    }
-                }
-                return logger.level;
-            }
+        match self.find(path, None) {
+            Some(logger) => logger.level_at(line),
+            None => self.default_level(),
         }
+    }
 
-        global::get_level()
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn get_level_cached(&self, path: &str, line: u32, cache: &PatternCache) -> LogLevel {
+        match self.find(path, Some(cache)) {
+            Some(logger) => logger.level_at(line),
+            None => self.default_level(),
+        }
     }
 
     #[doc(hidden)]
-    pub fn log(&self, record: &'static RecordMeta, args: fmt::Arguments) {
-        let record = SyncRecord::new(record, time::get_time(), args, self.formatter.clone());
-        if !LOG_THREAD.load(Ordering::Relaxed) {
-            self.process(&record);
+    #[inline(always)]
+    pub fn get_level_at(&self, record: &RecordMeta) -> LogLevel {
+        self.get_level_cached(record.path, record.line, &record.pattern)
+    }
+
+    #[doc(hidden)]
+    pub fn log(&self, meta: &'static RecordMeta, args: fmt::Arguments) {
+        let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+        let thread_id = thread_id::get();
+        let ctx = RecordContext::new(seq, thread_id);
+        stats::record(meta);
+        let record = SyncRecord::new(meta, ctx, args, self.formatter.clone());
+        if (self.mfilters || self.filter.is_some()) && !self.accepts(meta, &record) {
+            stats::filtered();
+            return;
+        }
+        match *self.queue {
+            Some(ref queue) if LOG_THREAD.load(Ordering::Relaxed) && !testing::is_capturing() => {
+                let record = record.into();
+                let qidx = thread_id % QNUM;
+                assert!(qidx < QNUM);
+                queue[qidx].push(record);
+                SENT[qidx].fetch_add(1, Ordering::Relaxed);
+            },
+            _ => self.process(meta, &record),
+        }
+    }
+
+    // Checks the message against the global filter and the filter of the module rule.
+    // The rule is resolved the same way as the level of the callsite.
+    fn accepts(&self, meta: &RecordMeta, record: &dyn Record) -> bool {
+        let msg = record.msg();
+        if let Some(ref filter) = self.filter {
+            if !filter.accepts(&msg) {
+                return false;
+            }
+        }
+        if self.mfilters {
+            if let Some(filter) = self.find(meta.path, Some(&meta.pattern)).and_then(|logger| logger.filter.as_ref()) {
+                return filter.accepts(&msg);
+            }
+        }
+        true
+    }
+
+    #[inline(always)]
+    fn process(&self, meta: &RecordMeta, record: &dyn Record) {
+        self.dispatch(meta, record, false);
+    }
+
+    fn process_guarded(&self, record: &AsyncRecord) {
+        self.dispatch(record.meta(), record, true);
+    }
+
+    // Redacts the record, runs the filters and pushes the record into the handlers.
+    // The panics of the filters and handlers are caught if `guarded` is set.
+    #[inline(always)]
+    fn dispatch(&self, meta: &RecordMeta, record: &dyn Record, guarded: bool) {
+        if self.filters.is_empty() && self.redactions.is_empty() {
+            return self.emit(meta, record, guarded);
+        }
+
+        let formatter: &Arc<Formatter> = &self.formatter;
+        let redacted = if self.redactions.is_empty() {
+            None
         } else {
-            let record = record.into();
-            let qidx = thread_id::get() % QNUM;
-            assert!(qidx < QNUM);
-            self.queue[qidx].push(record);
-            SENT[qidx].fetch_add(1, Ordering::Relaxed);
+            redact::apply(&self.redactions, &record.msg())
+                .map(|msg| Amended::new(record, record.level(), msg, formatter))
+        };
+        let record: &dyn Record = match redacted {
+            Some(ref redacted) => redacted,
+            None => record,
+        };
+
+        if self.filters.is_empty() {
+            return self.emit(meta, record, guarded);
+        }
+        let level = if guarded {
+            filters::guard(&self.filters, record, formatter)
+        } else {
+            filters::run(&self.filters, record, formatter)
+        };
+        match level {
+            Some(level) if level != record.level() => {
+                self.emit(meta, &Amended::new(record, level, record.msg(), formatter), guarded);
+            },
+            Some(_) => self.emit(meta, record, guarded),
+            None => stats::filtered(),
         }
     }
 
+    // The route of the callsite is cached the same way as the pattern rule.
+    fn route(&self, meta: &RecordMeta) -> Route<'_> {
+        let idx = match meta.route.get(self.pgen) {
+            Some(found) => found.map(|(idx, _)| idx),
+            None => {
+                let idx = self.routes.lookup(meta.path);
+                meta.route.set(self.pgen, idx.map(|idx| (idx, 0)));
+                idx
+            },
+        };
+        self.routes.get(idx)
+    }
+
     #[inline(always)]
-    fn process(&self, record: &Record) {
+    fn emit(&self, meta: &RecordMeta, record: &dyn Record, guarded: bool) {
+        if testing::is_active() {
+            testing::push(record);
+        }
         if self.handlers.is_empty() {
             ::handlers::stdout::emit(&record.formatted());
         } else {
-            for h in self.handlers.deref() {
-                h(record);
+            // The route is resolved once for all handlers.
+            let route = if self.routes.is_empty() {
+                None
+            } else {
+                Some(self.route(meta))
+            };
+            for (idx, h) in self.handlers.iter().enumerate() {
+                if route.as_ref().is_some_and(|route| !route.allows(idx)) {
+                    continue;
+                }
+                if guarded {
+                    ::handlers::guard(h.as_ref(), record);
+                } else {
+                    h.emit(record);
+                }
             }
         }
     }
+
+    fn flush(&self) {
+        for h in self.handlers.deref() {
+            h.flush();
+        }
+    }
 }
 
 fn qempty() -> bool {
+    queue_depth() == 0
+}
+
+pub(crate) fn queue_depth() -> usize {
     let sent = SENT.iter().fold(0, |sum, sent| sum + sent.load(Ordering::Relaxed));
     let received = RECEIVED.load(Ordering::Relaxed);
-    sent == received
+    sent.saturating_sub(received)
 }
 
-fn lthread(root: Arc<RwLock<RootLogger>>, queues: Arc<QVec>) {
+type Heads = [Option<AsyncRecord>; QNUM];
+
+fn consume(root: &RwLock<RootLogger>, queues: &QVec) -> usize {
+    let mut received: usize = 0;
+    for queue in queues.iter() {
+        if let Some(record) = queue.try_pop() {
+            {
+                let root = root.read();
+                root.process_guarded(&record);
+            }
+            received += 1;
+        }
+        thread::yield_now();
+    }
+    received
+}
+
+// K-way merge of the queues.
+// The heads of the queues are kept aside until the record with the lowest
+// sequence number is found among the available ones.
+fn consume_ordered(root: &RwLock<RootLogger>, queues: &QVec, heads: &mut Heads) -> usize {
+    let mut received: usize = 0;
+    while received < QNUM {
+        for (queue, head) in queues.iter().zip(heads.iter_mut()) {
+            if head.is_none() {
+                *head = queue.try_pop();
+            }
+        }
+
+        let next = heads.iter()
+            .enumerate()
+            .filter_map(|(idx, head)| head.as_ref().map(|record| (record.seq(), idx)))
+            .min();
+        match next {
+            Some((_, idx)) => {
+                let record = heads[idx].take().unwrap();
+                let root = root.read();
+                root.process_guarded(&record);
+                received += 1;
+            },
+            None => break,
+        }
+    }
+    received
+}
+
+fn lthread(root: Arc<RwLock<RootLogger>>, queues: Arc<QVec>, ordered: bool) {
     const BWAIT_MS: u64 = 10;
     #[cfg(not(test))] const RWAIT_MS: u64 = 500;
     #[cfg(test)] const RWAIT_MS: u64 = 10;
 
+    let mut heads: Heads = array::from_fn(|_| None);
+
     loop {
         'wait: loop {
             let now = Instant::now();
             while qempty() {
+                if STOP.load(Ordering::Relaxed) {
+                    return;
+                }
                 thread::yield_now();
                 if now.elapsed() > Duration::from_millis(BWAIT_MS) {
-                    thread::sleep(Duration::from_millis(RWAIT_MS));
+                    thread::park_timeout(Duration::from_millis(RWAIT_MS));
                     continue 'wait;
                 }
             }
@@ -268,19 +797,13 @@ fn lthread(root: Arc<RwLock<RootLogger>>, queues: Arc<QVec>) {
         }
 
         loop {
-            let mut received: usize = 0;
-            for queue in queues.iter() {
-                if let Some(record) = queue.try_pop() {
-                    {
-                        let root = root.read();
-                        root.process(&record);
-                    }
-                    received += 1;
-                }
-                thread::yield_now();
-            }
+            let received = if ordered {
+                consume_ordered(&root, &queues, &mut heads)
+            } else {
+                consume(&root, &queues)
+            };
             RECEIVED.fetch_add(received, Ordering::Relaxed);
-            if received < QNUM {
+            if received != QNUM {
                 break;
             }
             thread::yield_now();
@@ -292,30 +815,30 @@ type RL = Arc<RwLock<RootLogger>>;
 
 #[inline(always)]
 fn mkroot() -> RL {
-    static mut ROOT: *const RL = 0 as *const RL;
-    let mut queues: QVec = unsafe { mem::uninitialized() };
-    for queue in queues.iter_mut() {
-        let z = mem::replace(queue, SegQueue::new());
-        mem::forget(z);
-    }
+    let queues: QVec = array::from_fn(|_| SegQueue::new());
     let queues = Arc::new(queues);
 
     assert!(IS_INIT.load(Ordering::Relaxed));
-    let root = Arc::new(RwLock::new(RootLogger::new(queues.clone())));
+    Arc::new(RwLock::new(RootLogger::new(queues)))
+}
 
-    if LOG_THREAD.load(Ordering::Relaxed) {
-        let root = root.clone();
-        thread::spawn(move || {
-            lthread(root, queues);
-        });
-        { // warm up lazy statics
-            sync();
-        }
-    }
+fn spawn() {
+    let root = ROOT.clone();
+    let queues = root.read().queue.clone().unwrap();
+    let ordered = ORDERED.load(Ordering::Relaxed);
+    STOP.store(false, Ordering::Relaxed);
+    let handle = thread::spawn(move || {
+        lthread(root, queues, ordered);
+    });
+    *LTHREAD.lock().unwrap() = Some(handle);
+}
 
-    unsafe {
-        ROOT = mem::transmute(Box::new(root));
-        (*ROOT).clone()
+fn join() {
+    let handle = LTHREAD.lock().unwrap().take();
+    if let Some(handle) = handle {
+        STOP.store(true, Ordering::Relaxed);
+        handle.thread().unpark();
+        let _ = handle.join();
     }
 }
 
@@ -333,102 +856,447 @@ pub fn uproot(root: &RefCell<RootLogger>) {
 
 thread_local! (
     #[doc(hidden)]
-    pub static LROOT: RefCell<RootLogger> = RefCell::new(RootLogger::new(ROOT.read().queue.clone()));
-    static LGEN: RefCell<usize> = RefCell::new(0);
+    pub static LROOT: RefCell<RootLogger> = RefCell::new(RootLogger::new(ROOT.read().queue.clone().unwrap()));
+    static LGEN: RefCell<usize> = const { RefCell::new(0) };
 );
 
-lazy_static! {
-    #[doc(hidden)]
-    pub static ref ROOT: RL = mkroot();
-}
+lazy_static! {
+    #[doc(hidden)]
+    pub static ref ROOT: RL = mkroot();
+}
+
+fn drain() {
+    let mut heads: Heads = array::from_fn(|_| None);
+    while !qempty() {
+        if LTHREAD.lock().unwrap().is_none() {
+            // Nobody is going to consume the leftovers of the stopped log thread
+            let root = ROOT.clone();
+            let queues = root.read().queue.clone().unwrap();
+            let received = consume_ordered(&root, &queues, &mut heads);
+            RECEIVED.fetch_add(received, Ordering::Relaxed);
+            continue;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Ensures that the logging queue is completely consumed by the log thread
+/// and flushes all log handlers.
+///
+/// Normally this should be called in the very end of the program execution
+/// to ensure that all log records are properly flushed.
+pub fn sync() {
+    drain();
+    ROOT.read().flush();
+}
+
+#[doc(hidden)]
+pub fn reset() {
+    sync();
+    let mut root = ROOT.write();
+    global::set_level(LogLevel::WARN);
+    global::set_loggers(false);
+    root.reset();
+}
+
+fn mkformatter(spec: &spec::Root, name: &str) -> Result<Formatter, String> {
+    match spec.pattern(name) {
+        Some(fmt) => pattern::formatter(fmt).map_err(|err| format!("{:?}", err)),
+        None => Ok(Box::new(formatters::default::formatter)),
+    }
+}
+
+// The formatter, the handlers and the routes defined by the spec.
+type Built = (Formatter, Vec<Box<dyn Handler>>, Routes);
+
+// Creates the formatter and the handlers defined by the spec.
+fn build(spec: &spec::Root) -> Result<Built, String> {
+    let formatter = match spec.formatter {
+        Some(ref name) => mkformatter(spec, name)?,
+        None => Box::new(formatters::default::formatter),
+    };
+
+    let routes = Routes::build(spec)?;
+
+    let mut built = Vec::new();
+    for hspec in &spec.handlers {
+        let mut handler = match hspec.kind {
+            HandlerKind::Stdout => handlers::stdout::handler(),
+            HandlerKind::Stderr => handlers::stderr::handler(),
+            HandlerKind::File(ref path) => {
+                handlers::file::handler(Path::new(path))
+                    .map_err(|err| format!("{:?}", err))?
+            },
+            HandlerKind::RotatingFile(ref path, count, size) => {
+                handlers::rotating_file::handler(Path::new(path), count, size)
+                    .map_err(|err| format!("{:?}", err))?
+            },
+        };
+        if let Some(ref name) = hspec.formatter {
+            handler = wrap::formatter(handler, mkformatter(spec, name)?);
+        }
+        if let Some(level) = hspec.level {
+            handler = wrap::level(handler, level);
+        }
+        built.push(handler);
+    }
+
+    Ok((formatter, built, routes))
+}
+
+/// Replaces the whole logging configuration with the one defined
+/// by the JSON [spec](spec/index.html).
+///
+/// The log rules, handlers and formatter are dropped and the ones defined
+/// by the spec are applied.
+/// The global log level is set to `WARN` unless defined by the spec.
+/// The [filters](macro.wp_register_filter.html) and
+/// [redaction rules](macro.wp_register_redaction.html) are kept.
+///
+/// The previous configuration is left intact if the spec is invalid
+/// or any of the handlers can't be created.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     wp::configure(r#"{
+///         "level": "info",
+///         "formatters": {
+///             "short": "{level}: {msg}"
+///         },
+///         "handlers": [
+///             {"name": "out", "type": "stdout"},
+///             {"name": "err", "type": "stderr", "level": "error", "formatter": "short"}
+///         ],
+///         "modules": [
+///             {"path": "foo::db", "level": "debug", "handlers": ["err"]}
+///         ]
+///     }"#).unwrap();
+///
+///     assert_eq!(wp_get_level!(^), wp::LogLevel::INFO);
+///     assert_eq!(wp_get_level!("foo::db"), wp::LogLevel::DEBUG);
+///
+///     info!("Goes to stdout");
+///     error!("Goes to stdout and stderr");
+/// }
+///
+/// ```
+pub fn configure(spec: &str) -> Result<(), String> {
+    let spec = match ::spec::parse(spec) {
+        Ok(spec) => spec,
+        Err(err) => return Err(err.to_string()),
+    };
+    apply(&spec, true)
+}
+
+// Applies the spec to the global logger.
+// Unless `full` only the log rules are replaced.
+pub(crate) fn apply(spec: &spec::Root, full: bool) -> Result<(), String> {
+    let built = if full {
+        Some(build(spec)?)
+    } else {
+        None
+    };
+
+    sync();
+    let mut root = ROOT.write();
+    global::set_level(spec.level.unwrap_or(LogLevel::WARN));
+    global::set_loggers(false);
+    match built {
+        Some((formatter, handlers, routes)) => root.configure(spec, formatter, handlers, routes),
+        None => {
+            root.reset_loggers();
+            root.set_spec(spec)
+        },
+    }
+}
+
+#[doc(hidden)]
+pub fn init(config: &Config) {
+    let log_thread = match env::var("WP_LOG_THREAD") {
+        Ok(ref val) => {
+            let val = &val.to_lowercase()[..1];
+            val == "y" || val == "1"
+        }
+        _ => config.thread,
+    };
+    LOG_THREAD.store(log_thread, Ordering::Relaxed);
+    ORDERED.store(config.ordered, Ordering::Relaxed);
+    PREFIX_MATCH.store(config.prefix_match, Ordering::Relaxed);
+
+    // NOTE: it's not a real guard.
+    // The `init` function is supposed to be called once on init
+    // or after `shutdown`.
+    assert!(!IS_INIT.swap(true, Ordering::Relaxed));
+
+    meta::init(config);
+    imp::restart();
+    timestamp::set_style(config.timestamp.clone());
+
+    reset();
+
+    if log_thread {
+        spawn();
+    }
+
+    // An invalid spec mustn't prevent the program from running.
+    if let Ok(ref rust_log) = env::var("RUST_LOG") {
+        if let Err(err) = wp_set_level!(spec(rust_log)) {
+            handlers::stderr::emit(&format!("Invalid RUST_LOG: {}\n", err));
+        }
+    }
+}
+
+/// Stops the logging.
+///
+/// Ensures that the logging queue is completely consumed, flushes and closes
+/// all log handlers and stops the log thread.
+///
+/// The log rules, handlers and formatter are reset.
+///
+/// Afterwards the logging might be initialized again with
+/// the [wp_init](macro.wp_init!.html) macro and a different configuration.
+pub fn shutdown() {
+    if !IS_INIT.load(Ordering::Relaxed) {
+        return;
+    }
+
+    sync();
+    LOG_THREAD.store(false, Ordering::Relaxed);
+    join();
+    drain();
+
+    {
+        let mut root = ROOT.write();
+        global::set_level(LogLevel::WARN);
+        global::set_loggers(false);
+        root.reset();
+    }
+
+    IS_INIT.store(false, Ordering::Relaxed);
+}
+
+/// An independent logger.
+///
+/// The logger has its own log rules, handlers and formatter and isn't affected
+/// by the configuration of the global logger.
+///
+/// The logging macros use the global logger unless a logger is given explicitly
+/// with the `logger:` prefix.
+///
+/// The log records of an independent logger are always processed in the calling thread.
+/// The [threaded](handlers/threaded/fn.handler.html) handlers might be used
+/// to offload the log sinks.
+///
+/// The clones of the logger share the configuration.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// use std::sync::{Arc, Mutex};
+/// use std::ops::Deref;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     let logger = wp::Logger::new();
+///     logger.set_level(wp::LogLevel::INFO).unwrap();
+///     logger.set_module_level("foo::bar", wp::LogLevel::CRITICAL).unwrap();
+///     logger.set_formatter(Box::new(|record| {
+///         format!("{}:{}", record.level(), record.msg())
+///     }));
+///
+///     let out = Arc::new(Mutex::new(String::new()));
+///     {
+///         let out = out.clone();
+///         logger.register_handler(Box::new(move |record: &dyn wp::Record| {
+///             out.lock().unwrap().push_str(record.formatted().deref());
+///         }));
+///     }
+///
+///     info!(logger: logger, "foo");
+///     debug!(logger: logger, "bar");
+///     info!("not handled by the logger");
+///
+///     assert_eq!(logger.get_level(), wp::LogLevel::INFO);
+///     assert_eq!(logger.get_module_level("foo::bar::qux"), wp::LogLevel::CRITICAL);
+///     assert_eq!(wp_get_level!(^), wp::LogLevel::WARN);
+///
+///     assert_eq!(*out.lock().unwrap(), "INFO:foo".to_string());
+/// }
+///
+/// ```
+#[derive(Clone)]
+pub struct Logger {
+    root: Arc<RwLock<RootLogger>>,
+}
+
+impl Logger {
+    /// Creates a logger with the `WARN` log level.
+    ///
+    /// The log records are emitted into `stdout` until a handler is registered.
+    pub fn new() -> Self {
+        Logger {
+            root: Arc::new(RwLock::new(RootLogger::independent())),
+        }
+    }
+
+    /// Sets the log level of the logger and drops the per-module rules.
+    pub fn set_level(&self, level: LogLevel) -> Result<(), String> {
+        if level == LogLevel::UNSUPPORTED {
+            return Err("Unsupported log level".to_string());
+        }
+
+        let mut root = self.root.write();
+        root.reset_loggers();
+        root.level = Some(level);
+        Ok(())
+    }
+
+    /// Sets the log level for the module or file path.
+    ///
+    /// See documentation for the [wp_get_level](macro.wp_get_level.html)
+    /// for the details on the log level hierarchy.
+    pub fn set_module_level(&self, path: &str, level: LogLevel) -> Result<(), String> {
+        self.root.write().set_level(path, level)
+    }
+
+    /// Applies the log [spec](spec/index.html).
+    pub fn set_spec(&self, spec: &str) -> Result<(), String> {
+        let spec = match ::spec::parse(spec) {
+            Ok(spec) => spec,
+            Err(err) => return Err(err.to_string()),
+        };
+        if let Some(level) = spec.level {
+            self.set_level(level)?;
+        }
+
+        self.root.write().set_spec(&spec)
+    }
+
+    /// Replaces the whole configuration of the logger with the one defined
+    /// by the JSON [spec](spec/index.html).
+    ///
+    /// Same as [configure](fn.configure.html) but for the logger.
+    pub fn configure(&self, spec: &str) -> Result<(), String> {
+        let spec = match ::spec::parse(spec) {
+            Ok(spec) => spec,
+            Err(err) => return Err(err.to_string()),
+        };
+        let (formatter, handlers, routes) = build(&spec)?;
+
+        let mut root = self.root.write();
+        root.level = Some(spec.level.unwrap_or(LogLevel::WARN));
+        root.configure(&spec, formatter, handlers, routes)
+    }
+
+    /// Returns the log rules of the logger as a JSON [spec](spec/index.html).
+    ///
+    /// See documentation for the [wp_get_spec](macro.wp_get_spec.html) for the details.
+    pub fn get_spec(&self) -> String {
+        self.root.read().get_spec()
+    }
+
+    /// Returns the log level of the logger.
+    pub fn get_level(&self) -> LogLevel {
+        self.root.read().default_level()
+    }
+
+    /// Returns the log level for the module or file path.
+    pub fn get_module_level(&self, path: &str) -> LogLevel {
+        self.root.read().get_level_for_module(path)
+    }
+
+    /// Registers a log record [handler](handlers/trait.Handler.html).
+    ///
+    /// Same as for the global logger the log records are emitted into `stdout`
+    /// if no handler is registered.
+    pub fn register_handler(&self, handler: Box<dyn Handler>) {
+        self.root.write().handler(handler);
+    }
 
-/// Ensures that the logging queue is completely consumed by the log thread.
-///
-/// Normally this should be called in the very end of the program execution
-/// to ensure that all log records are properly flushed.
-pub fn sync() {
-    while !qempty() {
-        thread::sleep(Duration::from_millis(10));
+    /// Registers a log record [filter](filters/trait.Filter.html).
+    pub fn register_filter(&self, filter: Box<dyn Filter>) {
+        self.root.write().add_filter(filter);
     }
-}
 
-#[doc(hidden)]
-pub fn reset() {
-    sync();
-    let mut root = ROOT.write();
-    global::set_level(LogLevel::WARN);
-    global::set_loggers(false);
-    root.reset();
-}
+    /// Registers a [redaction](redact/index.html) rule.
+    pub fn register_redaction(&self, rule: redact::Rule) {
+        self.root.write().add_redaction(rule);
+    }
 
-#[doc(hidden)]
-pub fn init(config: &Config) {
-    let log_thread = match env::var("WP_LOG_THREAD") {
-        Ok(ref val) => {
-            let val = &val.to_lowercase()[..1];
-            val == "y" || val == "1"
-        }
-        _ => config.thread,
-    };
-    LOG_THREAD.store(log_thread, Ordering::Relaxed);
+    /// Sets a log record [formatter](formatters/type.Formatter.html).
+    pub fn set_formatter(&self, formatter: Formatter) {
+        self.root.write().formatter(formatter);
+    }
 
-    // NOTE: it's not a real guard.
-    // The `init` function is supposed to be called once on init.
-    assert!(!IS_INIT.swap(true, Ordering::Relaxed));
+    /// Flushes all log handlers of the logger.
+    pub fn sync(&self) {
+        self.root.read().flush();
+    }
 
-    reset();
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn root(&self) -> RwLockReadGuard<'_, RootLogger> {
+        self.root.read()
+    }
+}
 
-    if let Ok(ref rust_log) = env::var("RUST_LOG") {
-        wp_set_level!(spec(rust_log)).unwrap();
+impl Default for Logger {
+    fn default() -> Self {
+        Logger::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate tempdir;
+    use self::tempdir::TempDir;
+
     use super::*;
 
-    use levels::LEVELS;
+    use levels::{self, LEVELS};
+    use filters::Verdict;
     use line_range::LineRangeBound;
 
-    use std::sync::{Once, ONCE_INIT};
-    use std::sync::Mutex;
+    use std::sync::Once;
     use std::ops::Deref;
     use std::thread;
     use std::panic;
+    use std::fs;
 
     // NOTE: the test must not run in //
-    fn run_test<T>(test: T) where T: FnOnce(Arc<Mutex<String>>) -> () + panic::UnwindSafe {
-        struct TContext {
-            lock: Mutex<u32>,
-        };
-        static mut CONTEXT: *const Arc<TContext> = 0 as *const Arc<TContext>;
-        static ONCE: Once = ONCE_INIT;
-
-        let context = unsafe {
-            ONCE.call_once(|| {
-                let config = Config {
-                    thread: cfg!(feature = "test-thread-log"),
-                    ..Default::default()
-                };
-                wp_init!(&config);
-
-                let context = Arc::new(TContext {
-                    lock: Mutex::new(0),
-                });
-                CONTEXT = mem::transmute(Box::new(context));
-            });
-
-            (*CONTEXT).clone()
-        };
+    fn run_test<T>(test: T) where T: FnOnce(Arc<Mutex<String>>) + panic::UnwindSafe {
+        static LOCK: Mutex<()> = Mutex::new(());
+        static ONCE: Once = Once::new();
+
+        ONCE.call_once(|| {
+            let config = Config {
+                thread: cfg!(feature = "test-thread-log"),
+                ..Default::default()
+            };
+            wp_init!(&config);
+        });
 
-        let lock = context.lock.lock().unwrap();
+        let lock = LOCK.lock().unwrap();
 
         let result = panic::catch_unwind(|| {
             reset();
             let out = Arc::new(Mutex::new(String::new()));
             {
                 let out = out.clone();
-                wp_register_handler!(Box::new(move |record| {
+                wp_register_handler!(Box::new(move |record: &dyn Record| {
                     out.lock().unwrap().push_str(record.formatted().deref());
                 }));
             }
@@ -486,6 +1354,110 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_logger_segments() {
+        run_test(|_| {
+            wp_set_level!(LogLevel::INFO, "foo").unwrap();
+            wp_set_level!(LogLevel::ERROR, "foobar").unwrap();
+            wp_set_level!(LogLevel::DEBUG, "qux@src/db").unwrap();
+
+            assert_eq!(wp_get_level!("foo"), LogLevel::INFO);
+            assert_eq!(wp_get_level!("foo::bar"), LogLevel::INFO);
+            assert_eq!(wp_get_level!("foo@src/foo.rs"), LogLevel::INFO);
+            assert_eq!(wp_get_level!("foobar::baz"), LogLevel::ERROR);
+            assert_eq!(wp_get_level!("foox::baz"), LogLevel::WARN);
+            assert_eq!(wp_get_level!("qux@src/db/pool.rs"), LogLevel::DEBUG);
+            assert_eq!(wp_get_level!("qux@src/dbx.rs"), LogLevel::WARN);
+
+            // The siblings with the shared prefix are left intact
+            wp_set_level!(LogLevel::CRITICAL, "foo").unwrap();
+            assert_eq!(wp_get_level!("foobar::baz"), LogLevel::ERROR);
+
+            PREFIX_MATCH.store(true, Ordering::Relaxed);
+            let result = panic::catch_unwind(|| {
+                assert_eq!(wp_get_level!("foox::baz"), LogLevel::CRITICAL);
+                assert_eq!(wp_get_level!("qux@src/dbx.rs"), LogLevel::DEBUG);
+            });
+            PREFIX_MATCH.store(false, Ordering::Relaxed);
+            if let Err(err) = result {
+                panic::resume_unwind(err);
+            }
+        });
+    }
+
+    #[test]
+    fn test_logger_patterns() {
+        run_test(|_| {
+            wp_set_level!(spec("info,myapp=warn,*::db::*=debug,^myapp::(net|io)::=error")).unwrap();
+
+            assert_eq!(wp_get_level!("myapp::db::pool"), LogLevel::DEBUG);
+            assert_eq!(wp_get_level!("other::db::pool::conn"), LogLevel::DEBUG);
+            assert_eq!(wp_get_level!("myapp::db"), LogLevel::WARN);
+            assert_eq!(wp_get_level!("myapp::net::tcp"), LogLevel::ERROR);
+            assert_eq!(wp_get_level!("myapp::cache"), LogLevel::WARN);
+            assert_eq!(wp_get_level!("other"), LogLevel::INFO);
+
+            // The more specific plain rule wins
+            wp_set_level!(LogLevel::CRITICAL, "myapp::db::pool::conn").unwrap();
+            assert_eq!(wp_get_level!("myapp::db::pool::conn"), LogLevel::CRITICAL);
+            assert_eq!(wp_get_level!("myapp::db::pool"), LogLevel::DEBUG);
+
+            // The pattern is redefined
+            wp_set_level!(LogLevel::NOTICE, "*::db::*").unwrap();
+            assert_eq!(wp_get_level!("myapp::db::pool"), LogLevel::NOTICE);
+
+            wp_set_level!(LogLevel::TRACE, "woodpecker::**", [(LineRangeBound::BOF, LineRangeBound::EOF)]).unwrap();
+            assert_eq!(wp_get_level!(), LogLevel::TRACE);
+            wp_set_level!(LogLevel::ERROR, "woodpecker::**").unwrap();
+            wp_set_level!(LogLevel::WARN, "woodpecker::**", [(line!() + 1, line!() + 1)]).unwrap();
+            assert_eq!(wp_get_level!(), LogLevel::WARN);
+            assert_eq!(wp_get_level!(), LogLevel::ERROR);
+
+            assert!(wp_set_level!(LogLevel::INFO, "^foo(").is_err());
+            assert!(wp_set_level!(spec("^foo(=info")).is_err());
+
+            wp_set_level!(LogLevel::INFO).unwrap();
+            assert_eq!(wp_get_level!("myapp::db::pool"), LogLevel::INFO);
+        });
+    }
+
+    #[test]
+    fn test_logger_pattern_cache() {
+        run_test(|_| {
+            let foo = Logger::new();
+            let bar = Logger::new();
+            let out = Arc::new(RwLock::new(Vec::new()));
+            for logger in &[&foo, &bar] {
+                let out = out.clone();
+                logger.register_handler(Box::new(move |record: &dyn Record| {
+                    out.write().push(record.msg().to_string());
+                }));
+            }
+            foo.set_spec("woodpecker::*=info").unwrap();
+            bar.set_spec("woodpecker::*=error").unwrap();
+
+            // The same callsites are resolved against the different and the changing rules.
+            let emit = |logger: &Logger, msg: &str| {
+                info!(logger: logger, "{}", msg);
+                wp_get_level!() <= LogLevel::INFO
+            };
+            for _ in 0..2 {
+                assert!(!emit(&foo, "foo"));
+                emit(&bar, "bar");
+            }
+            foo.set_spec("error,woodpecker::*=critical").unwrap();
+            emit(&foo, "none");
+            bar.set_spec("woodpecker::**=debug").unwrap();
+            emit(&bar, "qux");
+            assert_eq!(*out.read(), vec!["foo", "foo", "qux"]);
+
+            wp_set_level!(spec("error,*::logger::*=trace")).unwrap();
+            assert!(emit(&foo, "none"));
+            wp_set_level!(spec("trace,*::logger::*=error")).unwrap();
+            assert!(!emit(&foo, "none"));
+        });
+    }
+
     #[test]
     #[should_panic(expected = "File path not specified")]
     fn test_set_level_range_0() {
@@ -557,7 +1529,7 @@ mod tests {
                         LogLevel::WARN => warn!("msg"),
                         LogLevel::ERROR => error!("msg"),
                         LogLevel::CRITICAL => critical!("msg"),
-                        LogLevel::LOG | LogLevel::UNSUPPORTED => panic!(),
+                        LogLevel::LOG | LogLevel::OFF | LogLevel::CUSTOM(_) | LogLevel::UNSUPPORTED => panic!(),
                     }
                     sync();
                     let mut output = buf.lock().unwrap();
@@ -664,9 +1636,9 @@ mod tests {
             let out = Arc::new(RwLock::new(String::new()));
             {
                 let out = out.clone();
-                wp_register_handler!(Box::new(move |record| {
+                wp_register_handler!(Box::new(move |record: &dyn Record| {
                     out.write().push_str(record.msg().deref());
-                    out.write().push_str("|");
+                    out.write().push('|');
                 }));
 
                 wp_set_level!(LogLevel::INFO).unwrap();
@@ -679,20 +1651,568 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_logger_sync() {
+        use std::sync::atomic::AtomicUsize;
+
+        struct Sink {
+            flushed: Arc<AtomicUsize>,
+        }
+
+        impl Handler for Sink {
+            fn emit(&self, _: &dyn Record) {}
+
+            fn flush(&self) {
+                self.flushed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        run_test(|_| {
+            let flushed = Arc::new(AtomicUsize::new(0));
+            wp_register_handler!(Box::new(Sink {
+                flushed: flushed.clone(),
+            }));
+            let before = flushed.load(Ordering::Relaxed);
+            sync();
+            assert_eq!(flushed.load(Ordering::Relaxed), before + 1);
+        });
+    }
+
+    #[test]
+    fn test_logger_reinit() {
+        run_test(|_| {
+            let thread = cfg!(feature = "test-thread-log");
+            for thread in &[!thread, thread] {
+                shutdown();
+                shutdown();
+                wp_init!(&Config {
+                    thread: *thread,
+                    ordered: *thread,
+                    app_name: Some("foo".to_string()),
+                    ..Default::default()
+                });
+                assert_eq!(meta::get().app_name, "foo");
+
+                let out = Arc::new(RwLock::new(String::new()));
+                {
+                    let out = out.clone();
+                    wp_register_handler!(Box::new(move |record: &dyn Record| {
+                        out.write().push_str(record.msg().deref());
+                    }));
+                }
+                warn!("msg");
+                sync();
+                assert_eq!(*out.read(), "msg".to_string());
+            }
+        });
+    }
+
+    #[test]
+    fn test_logger_capture() {
+        run_test(|_| {
+            wp_set_level!(LogLevel::INFO).unwrap();
+
+            let capture = testing::capture();
+            info!("foo");
+            {
+                let inner = testing::capture();
+                thread::spawn(|| {
+                    warn!("bar");
+                }).join().unwrap();
+                warn!("timeout");
+                debug!("qux");
+                wp_assert_logged!(WARN, contains "timeout");
+                wp_assert_not_logged!(contains "foo");
+                wp_assert_not_logged!(contains "bar");
+                wp_assert_not_logged!(DEBUG);
+                assert_eq!(inner.records().len(), 1);
+            }
+            wp_assert_logged!(INFO, contains "foo");
+            wp_assert_logged!(contains "timeout");
+
+            let records = capture.records();
+            assert_eq!(records.len(), 2);
+            assert!(records[1].elapsed >= records[0].elapsed);
+            assert!(records[1].delta > Duration::from_secs(0));
+            assert_eq!(records[1].elapsed - records[0].elapsed, records[1].delta);
+            assert_eq!(records[0].level, LogLevel::INFO);
+            assert_eq!(records[0].msg, "foo");
+            assert_eq!(records[1].module, this_module!());
+
+            capture.clear();
+            wp_assert_not_logged!(INFO);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a record at ERROR to be logged")]
+    fn test_logger_capture_fail() {
+        run_test(|_| {
+            let _capture = testing::capture();
+            wp_assert_logged!(ERROR);
+        });
+    }
+
+    #[test]
+    fn test_logger_configure() {
+        run_test(|_| {
+            let dir = TempDir::new("wp-cfg").unwrap();
+            let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+            let spec = |routed: &str| format!(r#"{{
+                "level": "info",
+                "formatter": "short",
+                "formatters": {{"short": "{{level}}:{{msg}}", "long": "{{level}}:{{module}}:{{msg}}"}},
+                "handlers": [
+                    {{"name": "a", "type": "file", "path": "{}"}},
+                    {{"name": "b", "type": "rotating_file", "path": "{}", "count": 2, "size": 1024,
+                      "level": "error", "formatter": "long"}}
+                ],
+                "modules": [
+                    {{"path": "{}", "level": "debug", "handlers": ["b"]}}
+                ]
+            }}"#, path("a.log"), path("b.log"), routed);
+            let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+
+            let logger = Logger::new();
+            logger.configure(&spec("foo")).unwrap();
+            assert_eq!(logger.get_level(), LogLevel::INFO);
+            assert_eq!(logger.get_module_level("foo::bar"), LogLevel::DEBUG);
+            debug!(logger: logger, "hidden");
+            info!(logger: logger, "info");
+            error!(logger: logger, "error");
+            logger.sync();
+            assert_eq!(read("a.log"), "INFO:info\nERROR:error\n");
+            let expect = format!("ERROR:{}:error\n", this_module!());
+            assert_eq!(read("b.log"), expect);
+
+            logger.configure(&spec(this_module!())).unwrap();
+            debug!(logger: logger, "debug");
+            info!(logger: logger, "info");
+            error!(logger: logger, "error");
+            logger.sync();
+            assert_eq!(read("a.log"), "INFO:info\nERROR:error\n");
+            assert_eq!(read("b.log"), format!("{}{}", expect, expect));
+
+            // The route cached at the callsite follows the configuration.
+            for routed in &["foo", this_file!()] {
+                logger.configure(&spec(routed)).unwrap();
+                error!(logger: logger, "routed");
+            }
+            logger.sync();
+            assert_eq!(read("a.log"), "INFO:info\nERROR:error\nERROR:routed\n");
+            let routed = format!("ERROR:{}:routed\n", this_module!());
+            assert_eq!(read("b.log"), format!("{}{}{}{}", expect, expect, routed, routed));
+
+            assert!(logger.configure(r#"{"handlers": [{"name": "a", "type": "foo"}]}"#).is_err());
+            let spec = format!(r#"{{"handlers": [{{"name": "a", "type": "file", "path": "{}"}}]}}"#,
+                               dir.path().to_str().unwrap());
+            assert!(logger.configure(&spec).is_err());
+            assert_eq!(logger.get_level(), LogLevel::INFO);
+        });
+    }
+
+    #[test]
+    fn test_logger_get_spec() {
+        run_test(|_| {
+            let logger = Logger::new();
+            logger.set_spec("info,foo=debug,foo::bar=critical,qux::**=trace,*::bar=error").unwrap();
+            logger.root.write().set_level_ranges("foo@foo.rs", line_range::spec(LogLevel::INFO, &[
+                (10, 20).into(), (30, LineRangeBound::EOF.into()).into(),
+            ]).unwrap()).unwrap();
+            logger.root.write().set_level_ranges("foo@foo.rs", line_range::spec(LogLevel::ERROR, &[
+                (22, 25).into(),
+            ]).unwrap()).unwrap();
+            assert_eq!(logger.get_module_level("qux::bar"), LogLevel::ERROR);
+
+            let spec = logger.get_spec();
+            let copy = Logger::new();
+            copy.set_spec(&spec).unwrap();
+            assert_eq!(copy.get_spec(), spec);
+            assert_eq!(copy.get_level(), LogLevel::INFO);
+            assert_eq!(copy.get_module_level("foo::qux"), LogLevel::DEBUG);
+            assert_eq!(copy.get_module_level("qux::bar"), LogLevel::ERROR);
+            assert_eq!(copy.root().get_level("foo@foo.rs", 15), LogLevel::INFO);
+            assert_eq!(copy.root().get_level("foo@foo.rs", 23), LogLevel::ERROR);
+            assert_eq!(copy.root().get_level("foo@foo.rs", 1000), LogLevel::INFO);
+            assert_eq!(copy.root().get_level("foo@foo.rs", 26), LogLevel::DEBUG);
+
+            // The last defined pattern wins.
+            logger.set_spec("*::bar=error,qux::**=trace").unwrap();
+            assert_eq!(logger.get_module_level("qux::bar"), LogLevel::TRACE);
+            copy.set_spec(&logger.get_spec()).unwrap();
+            assert_eq!(copy.get_module_level("qux::bar"), LogLevel::TRACE);
+        });
+    }
+
+    #[test]
+    fn test_logger_spec_lines() {
+        run_test(|_| {
+            let logger = Logger::new();
+            logger.set_spec(&format!("info,{0}:10-20=debug,{0}:25=error,src/foo.rs:*=critical",
+                                     this_file!())).unwrap();
+            let path = this_file!();
+            assert_eq!(logger.root().get_level(path, 9), LogLevel::INFO);
+            assert_eq!(logger.root().get_level(path, 10), LogLevel::DEBUG);
+            assert_eq!(logger.root().get_level(path, 25), LogLevel::ERROR);
+            assert_eq!(logger.root().get_level("foo@src/foo.rs", 42), LogLevel::CRITICAL);
+            assert_eq!(logger.root().get_level("bar::qux@src/foo.rs", 42), LogLevel::CRITICAL);
+            assert_eq!(logger.root().get_level("bar@src/foo/bar.rs", 42), LogLevel::INFO);
+
+            logger.register_handler(Box::new(|_: &dyn Record| {}));
+            let capture = testing::capture();
+            let line = line!() + 2;
+            logger.set_spec(&format!("{}:{}=trace", file!(), line)).unwrap();
+            trace!(logger: logger, "visible");
+            trace!(logger: logger, "hidden");
+            let records = capture.records();
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].msg, "visible");
+        });
+    }
+
+    #[test]
+    fn test_logger_filter() {
+        run_test(|buf| {
+            wp_set_level!(spec("info/!heartbeat")).unwrap();
+            info!("heartbeat 1");
+            info!("request 1");
+            debug!("request 2");
+            sync();
+            assert!(buf.lock().unwrap().contains("request 1"));
+            assert!(!buf.lock().unwrap().contains("heartbeat"));
+            assert!(!buf.lock().unwrap().contains("request 2"));
+            assert!(wp_get_spec!().contains(r#""message":"!heartbeat""#));
+
+            wp_set_level!(spec(&format!(r#"{{"level": "info", "modules": [
+                {{"path": "{}", "level": "info", "message": "!drop me"}}
+            ]}}"#, this_module!()))).unwrap();
+            info!("drop me");
+            info!("keep me");
+            sync();
+            assert!(!buf.lock().unwrap().contains("drop me"));
+            assert!(buf.lock().unwrap().contains("keep me"));
+            assert!(wp_get_spec!().contains(r#""message":"!drop me""#));
+
+            let logger = Logger::new();
+            let out = Arc::new(RwLock::new(String::new()));
+            {
+                let out = out.clone();
+                logger.register_handler(Box::new(move |record: &dyn Record| {
+                    out.write().push_str(record.formatted().deref());
+                }));
+            }
+            logger.set_formatter(Box::new(|record| format!("{}|", record.msg())));
+            logger.set_spec(&format!(r#"{{"level": "info", "modules": [
+                {{"path": "{}", "message": "^slow"}},
+                {{"path": "{}::child"}}
+            ]}}"#, this_module!(), this_module!())).unwrap();
+            info!(logger: logger, "slow query");
+            info!(logger: logger, "fast query");
+            assert_eq!(*out.read(), "slow query|");
+            assert!(logger.get_spec().contains(r#""message":"^slow""#));
+
+            logger.set_level(LogLevel::INFO).unwrap();
+            info!(logger: logger, "fast query");
+            assert_eq!(*out.read(), "slow query|fast query|");
+        });
+    }
+
+    #[test]
+    fn test_logger_filter_chain() {
+        run_test(|_| {
+            let logger = Logger::new();
+            let out = Arc::new(RwLock::new(String::new()));
+            {
+                let out = out.clone();
+                logger.register_handler(wrap::level(Box::new(move |record: &dyn Record| {
+                    out.write().push_str(record.formatted().deref());
+                }), LogLevel::WARN));
+            }
+            logger.set_formatter(Box::new(|record| {
+                format!("{}:{}|", record.level(), record.msg())
+            }));
+            logger.set_level(LogLevel::INFO).unwrap();
+
+            logger.register_filter(Box::new(|record: &dyn Record| {
+                match record.msg().as_str() {
+                    "health" => Verdict::Reject,
+                    "important" => Verdict::Level(LogLevel::ERROR),
+                    _ => Verdict::Accept,
+                }
+            }));
+            logger.register_filter(Box::new(|record: &dyn Record| {
+                if record.level() == LogLevel::ERROR {
+                    Verdict::Level(LogLevel::CRITICAL)
+                } else {
+                    Verdict::Accept
+                }
+            }));
+
+            warn!(logger: logger, "health");
+            info!(logger: logger, "important");
+            info!(logger: logger, "dropped by the handler");
+            warn!(logger: logger, "plain");
+            debug!(logger: logger, "important");
+            assert_eq!(*out.read(), "CRITICAL:important|WARN:plain|");
+        });
+    }
+
+    #[test]
+    fn test_logger_redaction() {
+        run_test(|_| {
+            let logger = Logger::new();
+            let out = Arc::new(RwLock::new(String::new()));
+            {
+                let out = out.clone();
+                logger.register_handler(Box::new(move |record: &dyn Record| {
+                    out.write().push_str(&format!("{}#{}", record.msg(), record.formatted()));
+                }));
+            }
+            logger.set_formatter(Box::new(|record| format!("{}:{}|", record.level(), record.msg())));
+            logger.register_redaction(redact::emails());
+            logger.register_filter(Box::new(|record: &dyn Record| {
+                if record.msg().contains("[REDACTED]") {
+                    Verdict::Level(LogLevel::CRITICAL)
+                } else {
+                    Verdict::Accept
+                }
+            }));
+
+            warn!(logger: logger, "mail joe@example.com");
+            assert_eq!(*out.read(), "mail [REDACTED]#CRITICAL:mail [REDACTED]|");
+            out.write().clear();
+
+            warn!(logger: logger, "nothing");
+            assert_eq!(*out.read(), "nothing#WARN:nothing|");
+            out.write().clear();
+
+            logger.configure(r#"{"level": "info"}"#).unwrap();
+            assert_eq!(logger.root.read().redactions.len(), 1);
+            assert_eq!(logger.root.read().filters.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_logger_capture_dispatched() {
+        run_test(|buf| {
+            wp_register_redaction!(redact::emails());
+
+            let capture = testing::capture();
+            warn!("mail joe@example.com");
+            wp_assert_logged!(WARN, contains "[REDACTED]");
+            wp_assert_not_logged!(contains "joe@example.com");
+            assert_eq!(capture.records()[0].msg, "mail [REDACTED]");
+            capture.clear();
+
+            wp_register_filter!(Box::new(|record: &dyn Record| {
+                match record.msg().as_str() {
+                    "noise" => Verdict::Reject,
+                    "alert" => Verdict::Level(LogLevel::CRITICAL),
+                    _ => Verdict::Accept,
+                }
+            }));
+            warn!("noise");
+            warn!("alert");
+            wp_assert_not_logged!(contains "noise");
+            wp_assert_not_logged!(WARN);
+            wp_assert_logged!(CRITICAL, contains "alert");
+            assert_eq!(capture.records().len(), 1);
+            drop(capture);
+
+            sync();
+            assert!(buf.lock().unwrap().contains("mail [REDACTED]"));
+        });
+    }
+
+    #[test]
+    fn test_logger_stats() {
+        run_test(|_| {
+            stats::reset();
+            stats::track_formatting(true);
+            assert_eq!(::stats().records, 0);
+
+            let logger = Logger::new();
+            logger.register_handler(Box::new(|record: &dyn Record| {
+                let _ = record.formatted();
+            }));
+            logger.set_spec("info/!noise").unwrap();
+            info!(logger: logger, "foo");
+            warn!(logger: logger, "noise");
+            debug!(logger: logger, "bar");
+
+            info!(logger: logger, "foo");
+
+            let stats = ::stats();
+            stats::track_formatting(false);
+            assert_eq!(stats.records, 3);
+            assert_eq!(stats.levels, vec![(LogLevel::INFO, 2), (LogLevel::WARN, 1)]);
+            assert_eq!(stats.modules, vec![(this_module!().to_string(), 3)]);
+            assert_eq!(stats.filtered, 1);
+            assert!(stats.formatting > Duration::from_secs(0));
+
+            stats::reset();
+            let stats = ::stats();
+            assert_eq!(stats.records, 0);
+            assert!(stats.levels.is_empty());
+            assert!(stats.modules.is_empty());
+            assert_eq!(stats.filtered, 0);
+            assert_eq!(stats.formatting, Duration::from_secs(0));
+        });
+    }
+
+    #[test]
+    fn test_logger_off() {
+        run_test(|buf| {
+            wp_set_level!(spec(&format!("all,{}=off", this_module!()))).unwrap();
+            assert_eq!(wp_get_level!(^), LogLevel::TRACE);
+            assert_eq!(wp_get_level!(), LogLevel::OFF);
+            critical!("critical");
+            log!("unconditional");
+
+            let logger = Logger::new();
+            logger.set_spec("off").unwrap();
+            critical!(logger: logger, "critical");
+            log!(logger: logger, "unconditional");
+
+            wp_set_level!(LogLevel::OFF).unwrap();
+            log!("unconditional");
+
+            sync();
+            assert!(buf.lock().unwrap().is_empty());
+        });
+    }
+
+    wp_custom_level!(secure, 25);
+
+    #[test]
+    fn test_logger_custom_level() {
+        run_test(|buf| {
+            let level = levels::register("secure", 25).unwrap();
+
+            let logger = Logger::new();
+            let out = Arc::new(RwLock::new(String::new()));
+            {
+                let out = out.clone();
+                logger.register_handler(Box::new(move |record: &dyn Record| {
+                    out.write().push_str(record.formatted().deref());
+                }));
+            }
+            logger.set_formatter(Box::new(|record| {
+                format!("{}:{}|", record.level(), record.msg())
+            }));
+
+            logger.set_spec(&format!("warn,{}=secure", this_module!())).unwrap();
+            assert_eq!(logger.get_module_level(this_module!()), level);
+            warn!(logger: logger, "warn");
+            secure!(logger: logger, "secure");
+            error!(logger: logger, "error");
+            assert_eq!(*out.read(), "SECURE:secure|ERROR:error|");
+            assert!(logger.get_spec().contains("\"SECURE\""));
+
+            wp_set_level!(LogLevel::ERROR).unwrap();
+            secure!("not seen");
+            wp_set_level!(level).unwrap();
+            secure!("seen");
+            sync();
+            assert!(buf.lock().unwrap().contains("SECURE"));
+            assert!(buf.lock().unwrap().contains("seen"));
+            assert!(!buf.lock().unwrap().contains("not seen"));
+
+            // The unregistered level is kept as is.
+            wp_set_level!(spec(r#"{"level": 27}"#)).unwrap();
+            assert_eq!(wp_get_level!(^), LogLevel::CUSTOM(27));
+            trace!("trace is filtered");
+            error!("error is seen");
+            sync();
+            assert!(!buf.lock().unwrap().contains("trace is filtered"));
+            assert!(buf.lock().unwrap().contains("error is seen"));
+            assert_eq!(spec::parse(&wp_get_spec!()).unwrap().level, Some(LogLevel::CUSTOM(27)));
+        });
+    }
+
+    #[test]
+    fn test_logger_independent() {
+        run_test(|buf| {
+            let logger = Logger::new();
+            let out = Arc::new(RwLock::new(String::new()));
+            {
+                let out = out.clone();
+                logger.register_handler(Box::new(move |record: &dyn Record| {
+                    out.write().push_str(record.formatted().deref());
+                }));
+            }
+            logger.set_formatter(Box::new(|record| {
+                format!("{}:{}|", record.level(), record.msg())
+            }));
+
+            assert_eq!(logger.get_level(), LogLevel::WARN);
+            logger.set_spec(&format!("info,foo=critical,{}=error", this_file!())).unwrap();
+            assert_eq!(logger.get_level(), LogLevel::INFO);
+            assert_eq!(logger.get_module_level("foo::bar"), LogLevel::CRITICAL);
+            assert_eq!(logger.get_module_level("bar"), LogLevel::INFO);
+            assert_eq!(wp_get_level!(^), LogLevel::WARN);
+            assert_eq!(wp_get_level!(), LogLevel::WARN);
+
+            logger.set_level(LogLevel::DEBUG).unwrap();
+            assert!(logger.set_level(LogLevel::UNSUPPORTED).is_err());
+            logger.set_module_level(this_module!(), LogLevel::VERBOSE).unwrap();
+
+            let clone = logger.clone();
+            debug!(logger: logger, "hidden");
+            verbose!(logger: clone, "foo");
+            log!(logger: &logger, "bar");
+            error!("global");
+
+            sync();
+            assert_eq!(*out.read(), "VERBOSE:foo|LOG:bar|".to_string());
+            let output = buf.lock().unwrap();
+            assert!(output.contains("global"));
+            assert!(!output.contains("foo"));
+        });
+    }
+
+    #[test]
+    fn test_logger_ordered() {
+        static RECORD: RecordMeta = RecordMeta::new(LogLevel::INFO, "foo", "foo.rs", "foo@foo.rs", 42);
+
+        let root = RwLock::new(RootLogger::independent());
+        let out = Arc::new(RwLock::new(Vec::new()));
+        {
+            let out = out.clone();
+            root.write().handler(Box::new(move |record: &dyn Record| {
+                out.write().push(record.seq());
+            }));
+        }
+
+        let queues: QVec = array::from_fn(|_| SegQueue::new());
+        let formatter = root.read().formatter.clone();
+        for &(qidx, seq) in &[(3, 1), (0, 2), (3, 4), (1, 0), (0, 3), (QNUM - 1, 5)] {
+            let ctx = RecordContext::new(seq, 0);
+            queues[qidx].push(SyncRecord::new(&RECORD, ctx, format_args!("{}", seq), formatter.clone()).into());
+        }
+
+        let mut heads: Heads = array::from_fn(|_| None);
+        assert_eq!(consume_ordered(&root, &queues, &mut heads), 6);
+        assert!(heads.iter().all(Option::is_none));
+        assert_eq!(*out.read(), vec![0, 1, 2, 3, 4, 5]);
+    }
+
     #[test]
     fn test_logger_formatter() {
         run_test(|_| {
             let out = Arc::new(RwLock::new(String::new()));
             {
                 let out = out.clone();
-                wp_register_handler!(Box::new(move |record| {
+                wp_register_handler!(Box::new(move |record: &dyn Record| {
                     out.write().push_str(record.formatted().deref());
                 }));
                 wp_set_formatter!(Box::new(|record| {
                     assert!(record.ts().sec > 0);
+                    assert!(Arc::ptr_eq(&record.ts_formatted(), &record.ts_formatted()));
                     format!(
-                        "{}:{}|",
+                        "{}:{}:{}|",
                         record.level(),
+                        record.thread_name().unwrap_or("-"),
                         record.msg(),
                     )
                 }));
@@ -700,9 +2220,14 @@ mod tests {
                 wp_set_level!(LogLevel::INFO).unwrap();
                 info!("msg");
                 debug!("foo");
+                sync();
+                thread::Builder::new().name("worker".to_string()).spawn(|| {
+                    info!("bar");
+                }).unwrap().join().unwrap();
             }
             sync();
-            assert_eq!(*out.read(), "INFO:msg|".to_string());
+            let name = thread::current().name().unwrap_or("-").to_string();
+            assert_eq!(*out.read(), format!("INFO:{}:msg|INFO:worker:bar|", name));
         });
     }
 
@@ -713,7 +2238,7 @@ mod tests {
             let out = Arc::new(RwLock::new(String::new()));
             {
                 let out = out.clone();
-                wp_register_handler!(Box::new(move |record| {
+                wp_register_handler!(Box::new(move |record: &dyn Record| {
                     out.write().push_str(record.formatted().deref());
                 }));
                 wp_set_formatter!(Box::new(move |record| {
@@ -744,7 +2269,7 @@ mod tests {
             sync();
             let sum = out.read().split("INFO:").
                 filter(|val| !val.is_empty()).
-                fold(0, |acc, ref val| {
+                fold(0, |acc, val| {
                     acc + val.parse::<u32>().unwrap()
                 });
             let expected: u32 = (0..100).filter(|x| x % 2 == 0).sum();
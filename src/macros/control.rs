@@ -104,7 +104,7 @@ macro_rules! wp_set_level {
         $(
             let (from, to): (u32, u32) = ($from.into(), $to.into());
             lranges.push((from, to));
-        )*;
+        )*
         match $crate::line_range::from_vtuple(&lranges) {
             Ok(lranges) => {
                 wp_set_level!($level, $logger, lranges)
@@ -142,14 +142,9 @@ macro_rules! wp_set_level {
                 if let Some(level) = spec.level {
                     let _ = wp_set_level!(level);
                 }
-                || -> Result<(), String> {
-                    for module in &spec.modules {
-                        wp_set_level!(module.level, &module.path, &module.lranges)?;
-                    }
-                    Ok(())
-                }()
+                __wp_write_root!(set_spec(&spec))
             },
-            Err(err) => Err(format!("{:?}", err))
+            Err(err) => Err(err.to_string())
         }
     }};
 
@@ -180,6 +175,10 @@ macro_rules! wp_set_level {
 ///
 /// If there's no exact match the rules of the parent are applied.
 ///
+/// The parents are determined by the `::` boundaries of the module path
+/// and the `/` boundaries of the file path, e.g. the rule for `foo` doesn't apply to `foobar`.
+/// See [Config::prefix_match](struct.Config.html#structfield.prefix_match) for the opt-out.
+///
 /// # Example
 ///
 /// ```rust
@@ -225,8 +224,8 @@ macro_rules! wp_get_level {
 
     () => {{
         if $crate::global::has_loggers() {
-            let path = this_file!();
-            __wp_read_root!(get_level(path, line!()))
+            static CACHE: $crate::logger::PatternCache = $crate::logger::PatternCache::new();
+            __wp_read_root!(get_level_cached(this_file!(), line!(), &CACHE))
         } else {
             $crate::global::get_level()
         }
@@ -241,18 +240,56 @@ macro_rules! wp_get_level {
     }};
 }
 
+/// Gets the log rules as a JSON [spec](spec/index.html).
+///
+/// The spec defines the global log level and all the module, file
+/// and line range rules in effect.
+/// Once applied with [wp_set_level](macro.wp_set_level.html) it restores
+/// exactly the same log rules.
+///
+/// The handlers and formatters are not exported.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     wp_set_level!(spec("info,foo::bar=debug,*::db=trace")).unwrap();
+///     wp_set_level!(wp::LogLevel::CRITICAL, this_file!(), [(10u32, 20u32)]).unwrap();
+///     let spec = wp_get_spec!();
+///
+///     wp_set_level!(wp::LogLevel::WARN).unwrap();
+///     wp_set_level!(spec(&spec)).unwrap();
+///     assert_eq!(wp_get_spec!(), spec);
+///     assert_eq!(wp_get_level!("foo::bar"), wp::LogLevel::DEBUG);
+/// }
+///
+/// ```
+#[macro_export]
+macro_rules! wp_get_spec {
+    () => {{
+        __wp_read_root!(get_spec())
+    }};
+}
+
 /// Registers a log record handler.
 ///
 /// The handler takes a log record as an argument and pushes it into a custom sink.
 ///
+/// The handler is either a closure or a custom implementation of the
+/// [`Handler`](handlers/trait.Handler.html) trait that might also be flushed and closed.
+///
 /// By default if no log handler is registered `woodpecker` emits
 /// log records into `stdout`.
 ///
 /// If at least one handler is registered than the `stdout` handler
 /// must be registered explicitly if it's still desired.
 ///
-/// See the definition of the [`Handler`](handlers/type.Handler.html) type for the details.
-///
 /// # Example
 /// In this example string "foo" will be logged three times into `stdout`
 /// but only one caught by the log handler.
@@ -274,7 +311,7 @@ macro_rules! wp_get_level {
 ///         wp_register_handler!(wp::handlers::stdout::handler());
 ///         warn!("foo");
 ///         let out = out.clone();
-///         wp_register_handler!(Box::new(move |record| {
+///         wp_register_handler!(Box::new(move |record: &dyn wp::Record| {
 ///             out.lock().unwrap().push_str(record.msg().deref());
 ///         }));
 ///
@@ -294,6 +331,77 @@ macro_rules! wp_register_handler {
     }};
 }
 
+/// Registers a log record filter.
+///
+/// The [filter](filters/trait.Filter.html) sees each log record that passed the log level check
+/// before it's pushed into the handlers.
+/// It either accepts the record, rejects it or rewrites its log level.
+///
+/// The filters run in the order of registration.
+///
+/// See the [filters](filters/index.html) module for the details.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// use wp::filters::Verdict;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     wp_register_filter!(Box::new(|record: &dyn wp::Record| {
+///         if record.module().starts_with("hyper::") {
+///             Verdict::Reject
+///         } else {
+///             Verdict::Accept
+///         }
+///     }));
+///
+///     warn!("It's alive!");
+/// }
+///
+/// ```
+#[macro_export]
+macro_rules! wp_register_filter {
+    ($filter:expr) => {{
+        __wp_write_root!(add_filter($filter));
+    }};
+}
+
+/// Registers a redaction rule.
+///
+/// The [rule](redact/struct.Rule.html) masks the secrets and personal data in the log messages
+/// before the log records are seen by the filters and handlers.
+///
+/// See the [redact](redact/index.html) module for the details.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     wp_register_redaction!(wp::redact::credit_cards());
+///
+///     warn!("Charged 4111 1111 1111 1111");
+/// }
+///
+/// ```
+#[macro_export]
+macro_rules! wp_register_redaction {
+    ($rule:expr) => {{
+        __wp_write_root!(add_redaction($rule));
+    }};
+}
+
 /// Sets a log record formatter.
 ///
 /// A [default](formatters/default/fn.formatter.html) formatter is used if not set explicitly.
@@ -322,7 +430,7 @@ macro_rules! wp_register_handler {
 ///     let out = Arc::new(Mutex::new(String::new()));
 ///     {
 ///         let out = out.clone();
-///         wp_register_handler!(Box::new(move |record| {
+///         wp_register_handler!(Box::new(move |record: &dyn wp::Record| {
 ///             out.lock().unwrap().push_str(record.formatted().deref());
 ///         }));
 ///
@@ -33,3 +33,12 @@ macro_rules! __wp_read_root {
         })
     }};
 }
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __wp_static_enabled {
+    ($level:expr) => {{
+        const ENABLED: bool = $crate::levels::static_enabled($level);
+        ENABLED
+    }};
+}
@@ -22,6 +22,8 @@
 ///
 /// The `WP_LOG_THREAD` environment variable overrides the passed configuration.
 ///
+/// The logging might be initialized again only after [shutdown](fn.shutdown.html).
+///
 /// # Example
 ///
 /// ```rust
@@ -52,12 +54,12 @@
 /// use std::ops::Deref;
 ///
 /// fn main() {
-///     wp_init!(&wp::Config { thread: true, ..Default::default() });
+///     wp_init!(&wp::Config::default().thread(true));
 ///
 ///     let out = Arc::new(Mutex::new(String::new()));
 ///     {
 ///         let out = out.clone();
-///         wp_register_handler!(Box::new(move |record| {
+///         wp_register_handler!(Box::new(move |record: &dyn wp::Record| {
 ///             out.lock().unwrap().push_str(record.msg().deref());
 ///         }));
 ///
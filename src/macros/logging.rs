@@ -17,7 +17,12 @@
 /// Prepares and emits a log record if requested log [level](levels/enum.LogLevel.html) is greater or equal
 /// according to the log level.
 ///
-/// If log level is not specified then the log is emitted unconditionally.
+/// If log level is not specified then the log is emitted unless the log level
+/// at the current position is [OFF](levels/enum.LogLevel.html).
+///
+/// The log record is emitted via the global logger unless an independent
+/// [logger](struct.Logger.html) is given explicitly with the `logger:` prefix,
+/// e.g. `log!(logger: logger, "foo")` or `warn!(logger: logger, "foo")`.
 ///
 /// If, for example, the hierarchy rules deduce that the log level at the current position is
 /// [WARN](levels/enum.LogLevel.html) then the logs for
@@ -29,6 +34,9 @@
 /// See documentation for the [wp_get_level](macro.wp_get_level.html)
 /// for more details on the log level hierarchy.
 ///
+/// The log records below the [STATIC_MAX_LEVEL](levels/constant.STATIC_MAX_LEVEL.html)
+/// defined by the cargo features compile to nothing.
+///
 /// # Example
 ///
 /// ```rust
@@ -48,7 +56,7 @@
 ///     let out = Arc::new(Mutex::new(String::new()));
 ///     {
 ///         let out = out.clone();
-///         wp_register_handler!(Box::new(move |record| {
+///         wp_register_handler!(Box::new(move |record: &dyn wp::Record| {
 ///             out.lock().unwrap().push_str(record.msg().deref());
 ///         }));
 ///
@@ -69,39 +77,43 @@
 /// ```
 #[macro_export]
 macro_rules! log {
+    (logger: $logger:expr, $level:expr => $($arg:tt)*) => {{
+        if __wp_static_enabled!($level) {
+            use $crate::record::imp::RecordMeta;
+            static RECORD: RecordMeta = RecordMeta::new($level, this_module!(), file!(), this_file!(), line!());
+            let root = $logger.root();
+            if root.get_level_at(&RECORD) <= $level {
+                root.log(&RECORD, format_args!($($arg)*));
+            }
+        }
+    }};
+
+    (logger: $logger:expr, $($arg:tt)*) => {{
+        log!(logger: $logger, $crate::LogLevel::LOG => $($arg)*)
+    }};
+
     ($level:expr => $($arg:tt)*) => {{
-        use $crate::record::imp::RecordMeta;
-        static RECORD: RecordMeta = RecordMeta {
-            level: $level,
-            module: this_module!(),
-            file: file!(),
-            line: line!(),
-        };
-        if $crate::global::has_loggers() {
-            let path = this_file!();
-            $crate::logger::LROOT.with(|root| {
-                $crate::logger::uproot(&root);
-                let root = root.borrow();
-                if root.get_level(path, line!()) <= $level {
-                    root.log(&RECORD, format_args!($($arg)*));
+        if __wp_static_enabled!($level) {
+            use $crate::record::imp::RecordMeta;
+            static RECORD: RecordMeta = RecordMeta::new($level, this_module!(), file!(), this_file!(), line!());
+            if $crate::global::has_loggers() {
+                $crate::logger::LROOT.with(|root| {
+                    $crate::logger::uproot(&root);
+                    let root = root.borrow();
+                    if root.get_level_at(&RECORD) <= $level {
+                        root.log(&RECORD, format_args!($($arg)*));
+                    }
+                });
+            } else {
+                if $crate::global::get_level() <= $level {
+                    __wp_read_root!(log(&RECORD, format_args!($($arg)*)));
                 }
-            });
-        } else {
-            if $crate::global::get_level() <= $level {
-                __wp_read_root!(log(&RECORD, format_args!($($arg)*)));
             }
         }
     }};
 
     ($($arg:tt)*) => {{
-        use $crate::record::imp::RecordMeta;
-        static RECORD: RecordMeta = RecordMeta {
-            level: $crate::LogLevel::LOG,
-            module: this_module!(),
-            file: file!(),
-            line: line!(),
-        };
-        __wp_read_root!(log(&RECORD, format_args!($($arg)*)));
+        log!($crate::LogLevel::LOG => $($arg)*)
     }};
 }
 
@@ -110,8 +122,12 @@ macro_rules! log {
 /// See the [log](macro.log.html) macro for the details.
 #[macro_export]
 macro_rules! trace {
+    (logger: $logger:expr, $($arg:tt)*) => {
+        log!(logger: $logger, $crate::LogLevel::TRACE => $($arg)*)
+    };
+
     ($($arg:tt)*) => {
-        log!($crate::LogLevel::TRACE => $($arg)*);
+        log!($crate::LogLevel::TRACE => $($arg)*)
     };
 }
 
@@ -121,7 +137,7 @@ macro_rules! trace {
 #[macro_export]
 macro_rules! in_trace {
     ($block:block) => {
-        if wp_get_level!() <= $crate::LogLevel::TRACE {
+        if __wp_static_enabled!($crate::LogLevel::TRACE) && wp_get_level!() <= $crate::LogLevel::TRACE {
             $block;
         }
     }
@@ -132,8 +148,12 @@ macro_rules! in_trace {
 /// See the [log](macro.log.html) macro for the details.
 #[macro_export]
 macro_rules! debug {
+    (logger: $logger:expr, $($arg:tt)*) => {
+        log!(logger: $logger, $crate::LogLevel::DEBUG => $($arg)*)
+    };
+
     ($($arg:tt)*) => {
-        log!($crate::LogLevel::DEBUG  => $($arg)*);
+        log!($crate::LogLevel::DEBUG  => $($arg)*)
     };
 }
 
@@ -143,7 +163,7 @@ macro_rules! debug {
 #[macro_export]
 macro_rules! in_debug {
     ($block:block) => {
-        if wp_get_level!() <= $crate::LogLevel::DEBUG {
+        if __wp_static_enabled!($crate::LogLevel::DEBUG) && wp_get_level!() <= $crate::LogLevel::DEBUG {
             $block;
         }
     }
@@ -154,8 +174,12 @@ macro_rules! in_debug {
 /// See the [log](macro.log.html) macro for the details.
 #[macro_export]
 macro_rules! verbose {
+    (logger: $logger:expr, $($arg:tt)*) => {
+        log!(logger: $logger, $crate::LogLevel::VERBOSE => $($arg)*)
+    };
+
     ($($arg:tt)*) => {
-        log!($crate::LogLevel::VERBOSE => $($arg)*);
+        log!($crate::LogLevel::VERBOSE => $($arg)*)
     };
 }
 
@@ -165,7 +189,7 @@ macro_rules! verbose {
 #[macro_export]
 macro_rules! in_verbose {
     ($block:block) => {
-        if wp_get_level!() <= $crate::LogLevel::VERBOSE {
+        if __wp_static_enabled!($crate::LogLevel::VERBOSE) && wp_get_level!() <= $crate::LogLevel::VERBOSE {
             $block;
         }
     }
@@ -176,8 +200,12 @@ macro_rules! in_verbose {
 /// See the [log](macro.log.html) macro for the details.
 #[macro_export]
 macro_rules! info {
+    (logger: $logger:expr, $($arg:tt)*) => {
+        log!(logger: $logger, $crate::LogLevel::INFO => $($arg)*)
+    };
+
     ($($arg:tt)*) => {
-        log!($crate::LogLevel::INFO => $($arg)*);
+        log!($crate::LogLevel::INFO => $($arg)*)
     };
 }
 
@@ -187,7 +215,7 @@ macro_rules! info {
 #[macro_export]
 macro_rules! in_info {
     ($block:block) => {
-        if wp_get_level!() <= $crate::LogLevel::INFO {
+        if __wp_static_enabled!($crate::LogLevel::INFO) && wp_get_level!() <= $crate::LogLevel::INFO {
             $block;
         }
     }
@@ -198,8 +226,12 @@ macro_rules! in_info {
 /// See the [log](macro.log.html) macro for the details.
 #[macro_export]
 macro_rules! notice {
+    (logger: $logger:expr, $($arg:tt)*) => {
+        log!(logger: $logger, $crate::LogLevel::NOTICE => $($arg)*)
+    };
+
     ($($arg:tt)*) => {
-        log!($crate::LogLevel::NOTICE => $($arg)*);
+        log!($crate::LogLevel::NOTICE => $($arg)*)
     };
 }
 
@@ -209,7 +241,7 @@ macro_rules! notice {
 #[macro_export]
 macro_rules! in_notice {
     ($block:block) => {
-        if wp_get_level!() <= $crate::LogLevel::NOTICE {
+        if __wp_static_enabled!($crate::LogLevel::NOTICE) && wp_get_level!() <= $crate::LogLevel::NOTICE {
             $block;
         }
     }
@@ -220,8 +252,12 @@ macro_rules! in_notice {
 /// See the [log](macro.log.html) macro for the details.
 #[macro_export]
 macro_rules! warn {
+    (logger: $logger:expr, $($arg:tt)*) => {
+        log!(logger: $logger, $crate::LogLevel::WARN => $($arg)*)
+    };
+
     ($($arg:tt)*) => {
-        log!($crate::LogLevel::WARN => $($arg)*);
+        log!($crate::LogLevel::WARN => $($arg)*)
     };
 }
 
@@ -231,7 +267,7 @@ macro_rules! warn {
 #[macro_export]
 macro_rules! in_warn {
     ($block:block) => {
-        if wp_get_level!() <= $crate::LogLevel::WARN {
+        if __wp_static_enabled!($crate::LogLevel::WARN) && wp_get_level!() <= $crate::LogLevel::WARN {
             $block;
         }
     }
@@ -242,8 +278,12 @@ macro_rules! in_warn {
 /// See the [log](macro.log.html) macro for the details.
 #[macro_export]
 macro_rules! error {
+    (logger: $logger:expr, $($arg:tt)*) => {
+        log!(logger: $logger, $crate::LogLevel::ERROR => $($arg)*)
+    };
+
     ($($arg:tt)*) => {
-        log!($crate::LogLevel::ERROR => $($arg)*);
+        log!($crate::LogLevel::ERROR => $($arg)*)
     };
 }
 
@@ -253,7 +293,7 @@ macro_rules! error {
 #[macro_export]
 macro_rules! in_error {
     ($block:block) => {
-        if wp_get_level!() <= $crate::LogLevel::ERROR {
+        if __wp_static_enabled!($crate::LogLevel::ERROR) && wp_get_level!() <= $crate::LogLevel::ERROR {
             $block;
         }
     }
@@ -264,8 +304,12 @@ macro_rules! in_error {
 /// See the [log](macro.log.html) macro for the details.
 #[macro_export]
 macro_rules! critical {
+    (logger: $logger:expr, $($arg:tt)*) => {
+        log!(logger: $logger, $crate::LogLevel::CRITICAL => $($arg)*)
+    };
+
     ($($arg:tt)*) => {
-        log!($crate::LogLevel::CRITICAL => $($arg)*);
+        log!($crate::LogLevel::CRITICAL => $($arg)*)
     };
 }
 
@@ -275,8 +319,77 @@ macro_rules! critical {
 #[macro_export]
 macro_rules! in_critical {
     ($block:block) => {
-        if wp_get_level!() <= $crate::LogLevel::CRITICAL {
+        if __wp_static_enabled!($crate::LogLevel::CRITICAL) && wp_get_level!() <= $crate::LogLevel::CRITICAL {
             $block;
         }
     }
 }
+
+/// Defines the logging macro for a user-defined log level.
+///
+/// `wp_custom_level!(audit, 35)` defines the `audit!` macro that produces log records
+/// for the level `35` and accepts the `logger:` prefix the same way as the built-in macros.
+///
+/// The name of the level should be registered with the
+/// [register](levels/fn.register.html) function to be usable in the log specs
+/// and to be printed by name in the formatters.
+///
+/// See the [log](macro.log.html) macro for the details.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// use std::sync::{Arc, Mutex};
+/// use std::ops::Deref;
+///
+/// wp_custom_level!(audit, 35);
+///
+/// fn main() {
+///     wp_init!();
+///     wp::levels::register("audit", 35).unwrap();
+///     wp_set_level!(wp::LogLevel::ERROR).unwrap();
+///
+///     let out = Arc::new(Mutex::new(String::new()));
+///     {
+///         let out = out.clone();
+///         wp_register_handler!(Box::new(move |record: &dyn wp::Record| {
+///             let msg = format!("{}: {}", record.level(), record.msg().deref());
+///             out.lock().unwrap().push_str(&msg);
+///         }));
+///
+///         audit!("login {}", "root");
+///     }
+///
+///     wp_set_level!(spec("audit")).unwrap();
+///     error!("not seen");
+///
+///     if cfg!(feature = "test-thread-log") {
+///         wp::sync();
+///     }
+///
+///     assert_eq!(*out.lock().unwrap(), "AUDIT: login root");
+/// }
+/// ```
+#[macro_export]
+macro_rules! wp_custom_level {
+    ($name:ident, $value:expr) => {
+        macro_rules! __wp_custom_level {
+            ($d:tt) => {
+                macro_rules! $name {
+                    (logger: $d logger:expr, $d($d arg:tt)*) => {
+                        log!(logger: $d logger, $crate::LogLevel::CUSTOM($value) => $d($d arg)*)
+                    };
+
+                    ($d($d arg:tt)*) => {
+                        log!($crate::LogLevel::CUSTOM($value) => $d($d arg)*)
+                    };
+                }
+            };
+        }
+        __wp_custom_level!($);
+    };
+}
@@ -26,3 +26,6 @@ pub mod logging;
 
 #[macro_use]
 pub mod init;
+
+#[macro_use]
+pub mod testing;
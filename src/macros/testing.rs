@@ -0,0 +1,97 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Asserts that a matching log record was captured.
+///
+/// Optionally the log level and a substring of the message could be given.
+///
+/// Panics if there's no active [capture](testing/fn.capture.html) in the current thread.
+///
+/// See documentation of the [testing](testing/index.html) module for the details.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     let _capture = wp::testing::capture();
+///     error!("disk is full");
+///
+///     wp_assert_logged!(ERROR);
+///     wp_assert_logged!(ERROR, contains "full");
+///     wp_assert_logged!(contains "disk");
+/// }
+///
+/// ```
+#[macro_export]
+macro_rules! wp_assert_logged {
+    ($level:ident) => {{
+        $crate::testing::check(true, Some($crate::LogLevel::$level), None)
+    }};
+
+    ($level:ident, contains $needle:expr) => {{
+        $crate::testing::check(true, Some($crate::LogLevel::$level), Some($needle))
+    }};
+
+    (contains $needle:expr) => {{
+        $crate::testing::check(true, None, Some($needle))
+    }};
+}
+
+/// Asserts that no matching log record was captured.
+///
+/// Optionally the log level and a substring of the message could be given.
+///
+/// Panics if there's no active [capture](testing/fn.capture.html) in the current thread.
+///
+/// See documentation of the [testing](testing/index.html) module for the details.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate woodpecker;
+/// use woodpecker as wp;
+///
+/// fn main() {
+///     wp_init!();
+///
+///     let _capture = wp::testing::capture();
+///     warn!("disk is almost full");
+///
+///     wp_assert_not_logged!(ERROR);
+///     wp_assert_not_logged!(WARN, contains "timeout");
+///     wp_assert_not_logged!(contains "timeout");
+/// }
+///
+/// ```
+#[macro_export]
+macro_rules! wp_assert_not_logged {
+    ($level:ident) => {{
+        $crate::testing::check(false, Some($crate::LogLevel::$level), None)
+    }};
+
+    ($level:ident, contains $needle:expr) => {{
+        $crate::testing::check(false, Some($crate::LogLevel::$level), Some($needle))
+    }};
+
+    (contains $needle:expr) => {{
+        $crate::testing::check(false, None, Some($needle))
+    }};
+}
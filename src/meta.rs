@@ -0,0 +1,132 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Process metadata shared by all log records.
+//!
+//! The metadata is resolved once at [init](../fn.init.html).
+//! The host name is looked up only once per process.
+//!
+//! The application name and version are taken from the
+//! [configuration](../struct.Config.html).
+//! The application name defaults to the name of the executable
+//! while the version is empty if not configured.
+//!
+//! # Example
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate woodpecker;
+//! use woodpecker as wp;
+//!
+//! fn main() {
+//!     wp_init!(&wp::Config {
+//!         app_name: Some("myapp".to_string()),
+//!         app_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+//!         ..Default::default()
+//!     });
+//!
+//!     let meta = wp::meta::get();
+//!     assert_eq!(meta.pid, std::process::id());
+//!     assert_eq!(meta.app_name, "myapp");
+//!     assert_eq!(meta.app_version, env!("CARGO_PKG_VERSION"));
+//! }
+//!
+//! ```
+
+extern crate hostname;
+
+use std::env;
+use std::process;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use config::Config;
+
+/// The metadata of the process.
+#[derive(Clone, Debug)]
+pub struct Metadata {
+    /// Process identifier.
+    pub pid: u32,
+    /// Name of the host, empty if it can't be resolved.
+    pub hostname: String,
+    /// Name of the application.
+    pub app_name: String,
+    /// Version of the application.
+    pub app_version: String,
+}
+
+impl Metadata {
+    fn resolve(app_name: Option<&str>, app_version: Option<&str>) -> Self {
+        Metadata {
+            pid: process::id(),
+            hostname: HOSTNAME.clone(),
+            app_name: app_name.map_or_else(|| EXE_NAME.clone(), str::to_string),
+            app_version: app_version.unwrap_or_default().to_string(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref HOSTNAME: String = hostname::get()
+        .ok()
+        .and_then(|name| name.into_string().ok())
+        .unwrap_or_default();
+    static ref EXE_NAME: String = env::current_exe()
+        .ok()
+        .and_then(|path| path.file_stem().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+}
+
+// The metadata is leaked so that the references to it stay valid forever.
+// It's replaced only on init, hence the leak is negligible.
+static META: AtomicPtr<Metadata> = AtomicPtr::new(ptr::null_mut());
+
+/// Returns the metadata of the process.
+#[inline(always)]
+pub fn get() -> &'static Metadata {
+    let mut meta = META.load(Ordering::Acquire);
+    if meta.is_null() {
+        // Not initialized yet.
+        let resolved = Box::into_raw(Box::new(Metadata::resolve(None, None)));
+        meta = match META.compare_exchange(ptr::null_mut(), resolved, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => resolved,
+            Err(current) => current,
+        };
+    }
+    // The pointer comes from a leaked box.
+    unsafe { &*meta }
+}
+
+pub(crate) fn init(config: &Config) {
+    let meta = Metadata::resolve(config.app_name.as_deref(), config.app_version.as_deref());
+    META.store(Box::into_raw(Box::new(meta)), Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta() {
+        let meta = Metadata::resolve(Some("foo"), Some("1.2.3"));
+        assert_eq!(meta.pid, process::id());
+        assert_eq!(meta.app_name, "foo");
+        assert_eq!(meta.app_version, "1.2.3");
+
+        let meta = Metadata::resolve(None, None);
+        assert!(!meta.app_name.is_empty());
+        assert!(meta.app_version.is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+extern crate regex;
+use self::regex::Regex;
+
+use std::fmt;
+
+/// A glob pattern or an anchored regex matched against the module and file paths.
+///
+/// The path is a regex if it starts with `^`.
+///
+/// Otherwise it's a glob pattern if it contains `*` or `?`:
+///
+/// * `*` matches any part of a single path segment;
+/// * `?` matches any single character within a path segment;
+/// * `**` matches any number of path segments.
+///
+/// Same as for the plain paths, a pattern that matches a module also matches its children.
+#[derive(Clone)]
+pub struct PathPattern {
+    source: String,
+    regex: Regex,
+}
+
+impl fmt::Debug for PathPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PathPattern({:?})", self.source)
+    }
+}
+
+/// Checks whether the path is a glob pattern or a regex.
+pub fn is_pattern(path: &str) -> bool {
+    path.starts_with('^') || path.contains('*') || path.contains('?')
+}
+
+fn glob(pattern: &str) -> String {
+    let mut out = String::from("^");
+    let mut rest = pattern;
+    while !rest.is_empty() {
+        if rest.starts_with("**::") {
+            out.push_str("(?:.*::)?");
+            rest = &rest[4..];
+        } else if rest.starts_with("**") {
+            out.push_str(".*");
+            rest = &rest[2..];
+        } else if rest.starts_with("::**") && rest.len() == 4 {
+            out.push_str("(?:::.*)?");
+            rest = &rest[4..];
+        } else {
+            let c = rest.chars().next().unwrap();
+            match c {
+                '*' => out.push_str("[^:@/]*"),
+                '?' => out.push_str("[^:@/]"),
+                _ => out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    // The children of the matched module are matched as well
+    out.push_str("(?:::|@|/|$)");
+    out
+}
+
+impl PathPattern {
+    /// Compiles the pattern.
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let regex = if pattern.starts_with('^') {
+            pattern.to_string()
+        } else {
+            glob(pattern)
+        };
+        let regex = Regex::new(&regex)
+            .map_err(|err| format!("Invalid path pattern {:?}: {}", pattern, err))?;
+        Ok(PathPattern {
+            source: pattern.to_string(),
+            regex,
+        })
+    }
+
+    /// Returns the pattern as it was given.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the length of the matched part of the path if the pattern matches.
+    pub fn matches(&self, path: &str) -> Option<usize> {
+        self.regex.find(path).map(|m| m.end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        PathPattern::new(pattern).unwrap().matches(path).is_some()
+    }
+
+    #[test]
+    fn test_is_pattern() {
+        assert!(is_pattern("*::db::*"));
+        assert!(is_pattern("^foo::(bar|qux)"));
+        assert!(is_pattern("foo?"));
+        assert!(!is_pattern("foo::bar"));
+        assert!(!is_pattern("foo::bar@src/bar.rs"));
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(matches("*::db::*", "myapp::db::pool"));
+        assert!(matches("*::db::*", "myapp::db::pool::conn"));
+        assert!(matches("*::db::*", "myapp::db::pool@src/db/pool.rs"));
+        assert!(!matches("*::db::*", "myapp::db"));
+        assert!(!matches("*::db::*", "myapp::x::db::pool"));
+        assert!(matches("**::db::*", "myapp::x::db::pool"));
+        assert!(matches("**::db::*", "db::pool"));
+
+        assert!(matches("myapp::**::cache", "myapp::cache"));
+        assert!(matches("myapp::**::cache", "myapp::a::b::cache"));
+        assert!(matches("myapp::**::cache", "myapp::a::cache::lru"));
+        assert!(!matches("myapp::**::cache", "myapp::a::caches"));
+        assert!(!matches("myapp::**::cache", "other::cache"));
+        assert!(matches("myapp::**", "myapp::a::b"));
+        assert!(matches("myapp::**", "myapp"));
+
+        assert!(matches("foo?", "foo1::bar"));
+        assert!(!matches("foo?", "foo::bar"));
+        assert!(matches("*@src/db.rs", "myapp@src/db.rs"));
+        assert!(matches("myapp.*", "myapp.xyz"));
+        assert!(!matches("myapp.*", "myappx"));
+    }
+
+    #[test]
+    fn test_regex() {
+        assert!(matches("^myapp::(db|cache)::", "myapp::db::pool"));
+        assert!(!matches("^myapp::(db|cache)::", "myapp::net::pool"));
+        assert!(!matches("^db", "myapp::db"));
+        assert_eq!(PathPattern::new("^foo").unwrap().matches("foo::bar"), Some(3));
+        assert!(PathPattern::new("^foo(").is_err());
+    }
+}
@@ -21,6 +21,10 @@ extern crate parking_lot;
 use self::parking_lot::Mutex;
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::cell::{Cell, OnceCell};
+use std::time::{Duration, Instant};
+use std::thread;
 
 use std::fmt;
 use std::fmt::Write;
@@ -28,23 +32,102 @@ use std::fmt::Write;
 use std::marker::PhantomData;
 
 use formatters::Formatter;
+use formatters::timestamp;
 use levels::LogLevel;
+use logger::PatternCache;
 use record::Record;
+use stats;
 
 const PREALLOC: usize = 128;
 
-#[derive(Clone)]
 pub struct RecordMeta {
     pub level: LogLevel,
     pub module: &'static str,
     pub file: &'static str,
+    // The module and the file joined as in the log rules.
+    pub path: &'static str,
     pub line: u32,
+    pub(crate) pattern: PatternCache,
+    // The route to the handlers matched at the callsite.
+    pub(crate) route: PatternCache,
+    // Number of the records produced at the callsite, see the `stats` module.
+    pub(crate) count: AtomicU64,
+    pub(crate) registered: AtomicBool,
+}
+
+impl RecordMeta {
+    pub const fn new(level: LogLevel, module: &'static str, file: &'static str,
+                     path: &'static str, line: u32) -> Self {
+        RecordMeta {
+            level,
+            module,
+            file,
+            path,
+            line,
+            pattern: PatternCache::new(),
+            route: PatternCache::new(),
+            count: AtomicU64::new(0),
+            registered: AtomicBool::new(false),
+        }
+    }
+}
+
+lazy_static! {
+    static ref EPOCH: Instant = Instant::now();
+}
+// Nanoseconds elapsed since the EPOCH till the init.
+static START: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static LAST: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Resets the point in time the records' elapsed time is measured from.
+pub(crate) fn restart() {
+    START.store(EPOCH.elapsed().as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// The details of the record captured on the calling thread.
+#[derive(Clone)]
+pub(crate) struct RecordContext {
+    seq: u64,
+    ts: time::Timespec,
+    ts_mono: Instant,
+    elapsed: Duration,
+    delta: Duration,
+    thread_id: usize,
+    thread: thread::Thread,
+}
+
+impl RecordContext {
+    #[inline(always)]
+    pub(crate) fn new(seq: u64, thread_id: usize) -> Self {
+        let ts_mono = Instant::now();
+        let start = Duration::from_nanos(START.load(Ordering::Relaxed));
+        let elapsed = ts_mono.duration_since(*EPOCH).saturating_sub(start);
+        let delta = LAST.with(|last| {
+            let delta = last.get().map_or(Duration::from_secs(0), |last| ts_mono.duration_since(last));
+            last.set(Some(ts_mono));
+            delta
+        });
+        RecordContext {
+            seq,
+            ts: time::get_time(),
+            ts_mono,
+            elapsed,
+            delta,
+            thread_id,
+            thread: thread::current(),
+        }
+    }
 }
 
 struct RecordLazyMetaInner {
     msg: Option<Arc<String>>,
     formatted: Option<Arc<String>>,
     ts_utc: Option<Arc<DateTime<UTC>>>,
+    ts_local: Option<Arc<DateTime<Local>>>,
+    ts_formatted: Option<Arc<String>>,
 }
 
 #[inline(always)]
@@ -62,6 +145,8 @@ impl RecordLazyMetaInner {
             msg: None,
             formatted: None,
             ts_utc: None,
+            ts_local: None,
+            ts_formatted: None,
         }
     }
 
@@ -79,12 +164,32 @@ impl RecordLazyMetaInner {
 
     fn mk_ts_utc(&mut self, ts: &time::Timespec) {
         if self.ts_utc.is_none() {
-            let naive = chrono::NaiveDateTime::from_timestamp(ts.sec, ts.nsec as u32);
-            self.ts_utc = Some(Arc::new(chrono::DateTime::from_utc(naive, chrono::UTC)));
+            self.ts_utc = Some(Arc::new(utc(ts)));
+        }
+    }
+
+    fn mk_ts_local(&mut self, ts: &time::Timespec) {
+        if self.ts_local.is_none() {
+            self.mk_ts_utc(ts);
+            let ts_utc = self.ts_utc.as_ref().unwrap();
+            self.ts_local = Some(Arc::new(ts_utc.with_timezone(&Local)));
+        }
+    }
+
+    fn mk_ts_formatted(&mut self, ts: &time::Timespec) {
+        if self.ts_formatted.is_none() {
+            self.mk_ts_utc(ts);
+            let ts_utc = self.ts_utc.as_ref().unwrap();
+            self.ts_formatted = Some(Arc::new(timestamp::get_style().format(ts_utc)));
         }
     }
 }
 
+fn utc(ts: &time::Timespec) -> DateTime<UTC> {
+    let naive = chrono::NaiveDateTime::from_timestamp(ts.sec, ts.nsec as u32);
+    chrono::DateTime::from_utc(naive, chrono::UTC)
+}
+
 struct RecordLazyMeta {
     irecord: Mutex<RecordLazyMetaInner>,
     formatter: Arc<Formatter>,
@@ -95,7 +200,7 @@ impl RecordLazyMeta {
     fn new(formatter: Arc<Formatter>) -> Self {
         RecordLazyMeta {
             irecord: Mutex::new(RecordLazyMetaInner::new()),
-            formatter: formatter,
+            formatter,
         }
     }
 
@@ -113,14 +218,14 @@ impl RecordLazyMeta {
         msg.clone()
     }
 
-    fn formatted(&self, record: &Record) -> Arc<String> {
+    fn formatted(&self, record: &dyn Record) -> Arc<String> {
         {
             let irecord = self.irecord.lock();
             let format = irecord.formatted.is_none();
             drop(irecord);
 
             if format {
-                let formatted = Arc::new((self.formatter)(record));
+                let formatted = Arc::new(stats::format(&self.formatter, record));
                 let mut irecord = self.irecord.lock();
                 irecord.formatted = Some(formatted.clone());
                 return formatted;
@@ -138,26 +243,40 @@ impl RecordLazyMeta {
         let ts_utc = irecord.ts_utc.as_ref().unwrap();
         ts_utc.clone()
     }
+
+    fn ts_local(&self, ts: &time::Timespec) -> Arc<DateTime<Local>> {
+        let mut irecord = self.irecord.lock();
+        irecord.mk_ts_local(ts);
+        let ts_local = irecord.ts_local.as_ref().unwrap();
+        ts_local.clone()
+    }
+
+    fn ts_formatted(&self, ts: &time::Timespec) -> Arc<String> {
+        let mut irecord = self.irecord.lock();
+        irecord.mk_ts_formatted(ts);
+        let ts_formatted = irecord.ts_formatted.as_ref().unwrap();
+        ts_formatted.clone()
+    }
 }
 
 pub(crate) struct SyncRecord<'a> {
     irecord: &'static RecordMeta,
+    ctx: RecordContext,
     args: fmt::Arguments<'a>,
     precord: RecordLazyMeta,
-    ts: time::Timespec,
-    _oibit_remover: PhantomData<*mut Fn()>,
+    _oibit_remover: PhantomData<*mut dyn Fn()>,
 }
 
 impl<'a> SyncRecord<'a> {
     #[inline(always)]
     pub(crate) fn new(record: &'static RecordMeta,
-               ts: time::Timespec,
+               ctx: RecordContext,
                args: fmt::Arguments<'a>,
                formatter: Arc<Formatter>) -> Self {
         SyncRecord {
             irecord: record,
-            ts: ts,
-            args: args,
+            ctx,
+            args,
             precord: RecordLazyMeta::new(formatter),
             _oibit_remover: PhantomData,
         }
@@ -185,9 +304,39 @@ impl<'a> Record for SyncRecord<'a> {
         self.irecord.line
     }
 
+    #[inline(always)]
+    fn seq(&self) -> u64 {
+        self.ctx.seq
+    }
+
     #[inline(always)]
     fn ts(&self) -> time::Timespec {
-        self.ts
+        self.ctx.ts
+    }
+
+    #[inline(always)]
+    fn ts_mono(&self) -> Instant {
+        self.ctx.ts_mono
+    }
+
+    #[inline(always)]
+    fn elapsed(&self) -> Duration {
+        self.ctx.elapsed
+    }
+
+    #[inline(always)]
+    fn delta(&self) -> Duration {
+        self.ctx.delta
+    }
+
+    #[inline(always)]
+    fn thread_id(&self) -> usize {
+        self.ctx.thread_id
+    }
+
+    #[inline(always)]
+    fn thread_name(&self) -> Option<&str> {
+        self.ctx.thread.name()
     }
 
     fn msg(&self) -> Arc<String> {
@@ -199,15 +348,34 @@ impl<'a> Record for SyncRecord<'a> {
     }
 
     fn ts_utc(&self) -> Arc<DateTime<UTC>> {
-        self.precord.ts_utc(&self.ts)
+        self.precord.ts_utc(&self.ctx.ts)
+    }
+
+    fn ts_local(&self) -> Arc<DateTime<Local>> {
+        self.precord.ts_local(&self.ctx.ts)
+    }
+
+    fn ts_formatted(&self) -> Arc<String> {
+        self.precord.ts_formatted(&self.ctx.ts)
+    }
+
+    fn formatter(&self) -> Option<Arc<Formatter>> {
+        Some(self.precord.formatter.clone())
     }
 }
 
 pub(crate) struct AsyncRecord {
     irecord: &'static RecordMeta,
+    ctx: RecordContext,
     msg: String,
     precord: RecordLazyMeta,
-    ts: time::Timespec,
+}
+
+impl AsyncRecord {
+    #[inline(always)]
+    pub(crate) fn meta(&self) -> &'static RecordMeta {
+        self.irecord
+    }
 }
 
 impl Record for AsyncRecord {
@@ -231,9 +399,39 @@ impl Record for AsyncRecord {
         self.irecord.line
     }
 
+    #[inline(always)]
+    fn seq(&self) -> u64 {
+        self.ctx.seq
+    }
+
     #[inline(always)]
     fn ts(&self) -> time::Timespec {
-        self.ts
+        self.ctx.ts
+    }
+
+    #[inline(always)]
+    fn ts_mono(&self) -> Instant {
+        self.ctx.ts_mono
+    }
+
+    #[inline(always)]
+    fn elapsed(&self) -> Duration {
+        self.ctx.elapsed
+    }
+
+    #[inline(always)]
+    fn delta(&self) -> Duration {
+        self.ctx.delta
+    }
+
+    #[inline(always)]
+    fn thread_id(&self) -> usize {
+        self.ctx.thread_id
+    }
+
+    #[inline(always)]
+    fn thread_name(&self) -> Option<&str> {
+        self.ctx.thread.name()
     }
 
     fn msg(&self) -> Arc<String> {
@@ -245,7 +443,19 @@ impl Record for AsyncRecord {
     }
 
     fn ts_utc(&self) -> Arc<DateTime<UTC>> {
-        self.precord.ts_utc(&self.ts)
+        self.precord.ts_utc(&self.ctx.ts)
+    }
+
+    fn ts_local(&self) -> Arc<DateTime<Local>> {
+        self.precord.ts_local(&self.ctx.ts)
+    }
+
+    fn ts_formatted(&self) -> Arc<String> {
+        self.precord.ts_formatted(&self.ctx.ts)
+    }
+
+    fn formatter(&self) -> Option<Arc<Formatter>> {
+        Some(self.precord.formatter.clone())
     }
 }
 
@@ -254,9 +464,240 @@ impl<'a> From<SyncRecord<'a>> for AsyncRecord {
     fn from(orig: SyncRecord) -> AsyncRecord {
         AsyncRecord {
             irecord: orig.irecord,
+            ctx: orig.ctx,
             msg: format(orig.args),
             precord: orig.precord,
-            ts: orig.ts,
         }
     }
 }
+
+/// A self-contained copy of a log record that may be sent to another thread.
+///
+/// Only the message is rendered on the way, the rest is computed on demand
+/// by the thread that consumes the record.
+pub(crate) struct DetachedRecord {
+    level: LogLevel,
+    module: &'static str,
+    file: &'static str,
+    line: u32,
+    seq: u64,
+    ts: time::Timespec,
+    ts_mono: Instant,
+    elapsed: Duration,
+    delta: Duration,
+    thread_id: usize,
+    thread_name: Option<String>,
+    msg: Arc<String>,
+    formatter: Option<Arc<Formatter>>,
+    formatted: OnceCell<Arc<String>>,
+    ts_utc: OnceCell<Arc<DateTime<UTC>>>,
+    ts_local: OnceCell<Arc<DateTime<Local>>>,
+    ts_formatted: OnceCell<Arc<String>>,
+}
+
+impl Record for DetachedRecord {
+    #[inline(always)]
+    fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    #[inline(always)]
+    fn module(&self) -> &'static str {
+        self.module
+    }
+
+    #[inline(always)]
+    fn file(&self) -> &'static str {
+        self.file
+    }
+
+    #[inline(always)]
+    fn line(&self) -> u32 {
+        self.line
+    }
+
+    #[inline(always)]
+    fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    #[inline(always)]
+    fn ts(&self) -> time::Timespec {
+        self.ts
+    }
+
+    #[inline(always)]
+    fn ts_mono(&self) -> Instant {
+        self.ts_mono
+    }
+
+    #[inline(always)]
+    fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    #[inline(always)]
+    fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    #[inline(always)]
+    fn thread_id(&self) -> usize {
+        self.thread_id
+    }
+
+    #[inline(always)]
+    fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    fn msg(&self) -> Arc<String> {
+        self.msg.clone()
+    }
+
+    fn formatted(&self) -> Arc<String> {
+        self.formatted.get_or_init(|| {
+            // Either the formatter is known or the record is formatted upfront.
+            let formatter = self.formatter.as_ref().unwrap();
+            Arc::new(stats::format(formatter, self))
+        }).clone()
+    }
+
+    fn ts_utc(&self) -> Arc<DateTime<UTC>> {
+        self.ts_utc.get_or_init(|| Arc::new(utc(&self.ts))).clone()
+    }
+
+    fn ts_local(&self) -> Arc<DateTime<Local>> {
+        self.ts_local.get_or_init(|| Arc::new(self.ts_utc().with_timezone(&Local))).clone()
+    }
+
+    fn ts_formatted(&self) -> Arc<String> {
+        self.ts_formatted.get_or_init(|| Arc::new(timestamp::get_style().format(&self.ts_utc()))).clone()
+    }
+
+    fn formatter(&self) -> Option<Arc<Formatter>> {
+        self.formatter.clone()
+    }
+}
+
+impl From<&dyn Record> for DetachedRecord {
+    fn from(orig: &dyn Record) -> DetachedRecord {
+        let formatter = orig.formatter();
+        let formatted = match formatter {
+            Some(_) => OnceCell::new(),
+            None => OnceCell::from(orig.formatted()),
+        };
+        DetachedRecord {
+            level: orig.level(),
+            module: orig.module(),
+            file: orig.file(),
+            line: orig.line(),
+            seq: orig.seq(),
+            ts: orig.ts(),
+            ts_mono: orig.ts_mono(),
+            elapsed: orig.elapsed(),
+            delta: orig.delta(),
+            thread_id: orig.thread_id(),
+            thread_name: orig.thread_name().map(str::to_string),
+            msg: orig.msg(),
+            formatter,
+            formatted,
+            ts_utc: OnceCell::new(),
+            ts_local: OnceCell::new(),
+            ts_formatted: OnceCell::new(),
+        }
+    }
+}
+
+// A log record with the log level or message amended by a filter or redaction.
+// The record is formatted once again since the formatter might use both.
+pub(crate) struct Amended<'a> {
+    record: &'a dyn Record,
+    level: LogLevel,
+    msg: Arc<String>,
+    formatter: &'a Arc<Formatter>,
+    formatted: OnceCell<Arc<String>>,
+}
+
+impl<'a> Amended<'a> {
+    pub(crate) fn new(record: &'a dyn Record, level: LogLevel,
+                      msg: Arc<String>, formatter: &'a Arc<Formatter>) -> Self {
+        Amended {
+            record,
+            level,
+            msg,
+            formatter,
+            formatted: OnceCell::new(),
+        }
+    }
+}
+
+impl<'a> Record for Amended<'a> {
+    fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    fn module(&self) -> &'static str {
+        self.record.module()
+    }
+
+    fn file(&self) -> &'static str {
+        self.record.file()
+    }
+
+    fn line(&self) -> u32 {
+        self.record.line()
+    }
+
+    fn seq(&self) -> u64 {
+        self.record.seq()
+    }
+
+    fn ts(&self) -> time::Timespec {
+        self.record.ts()
+    }
+
+    fn ts_mono(&self) -> Instant {
+        self.record.ts_mono()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.record.elapsed()
+    }
+
+    fn delta(&self) -> Duration {
+        self.record.delta()
+    }
+
+    fn thread_id(&self) -> usize {
+        self.record.thread_id()
+    }
+
+    fn thread_name(&self) -> Option<&str> {
+        self.record.thread_name()
+    }
+
+    fn msg(&self) -> Arc<String> {
+        self.msg.clone()
+    }
+
+    fn formatted(&self) -> Arc<String> {
+        self.formatted.get_or_init(|| Arc::new(stats::format(self.formatter, self))).clone()
+    }
+
+    fn ts_utc(&self) -> Arc<DateTime<UTC>> {
+        self.record.ts_utc()
+    }
+
+    fn ts_local(&self) -> Arc<DateTime<Local>> {
+        self.record.ts_local()
+    }
+
+    fn ts_formatted(&self) -> Arc<String> {
+        self.record.ts_formatted()
+    }
+
+    fn formatter(&self) -> Option<Arc<Formatter>> {
+        Some(self.formatter.clone())
+    }
+}
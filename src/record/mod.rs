@@ -18,11 +18,55 @@ use self::chrono::prelude::*;
 extern crate time;
 
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use formatters::Formatter;
+use formatters::timestamp;
 use levels::LogLevel;
 
 /// Log record that holds information where log was recorded
 /// and the message details.
+///
+/// The methods added after the initial release have default implementations
+/// so that the existing implementations of the trait keep compiling.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate chrono;
+/// extern crate time;
+/// extern crate woodpecker;
+///
+/// use chrono::prelude::*;
+/// use std::sync::Arc;
+/// use woodpecker::{LogLevel, Record};
+///
+/// struct Replayed {
+///     msg: Arc<String>,
+///     ts: time::Timespec,
+/// }
+///
+/// impl Record for Replayed {
+///     fn level(&self) -> LogLevel { LogLevel::INFO }
+///     fn module(&self) -> &'static str { "replay" }
+///     fn file(&self) -> &'static str { "replay.rs" }
+///     fn line(&self) -> u32 { 1 }
+///     fn ts(&self) -> time::Timespec { self.ts }
+///     fn msg(&self) -> Arc<String> { self.msg.clone() }
+///     fn formatted(&self) -> Arc<String> { self.msg.clone() }
+///     fn ts_utc(&self) -> Arc<DateTime<UTC>> {
+///         let naive = NaiveDateTime::from_timestamp(self.ts.sec, self.ts.nsec as u32);
+///         Arc::new(DateTime::from_utc(naive, UTC))
+///     }
+/// }
+///
+/// fn main() {
+///     let record = Replayed { msg: Arc::new("foo".to_string()), ts: time::Timespec::new(0, 0) };
+///     assert_eq!(record.seq(), 0);
+///     assert_eq!(record.thread_name(), None);
+///     assert_eq!(record.ts_local().timestamp(), 0);
+/// }
+/// ```
 pub trait Record {
     /// Log level of the record.
     fn level(&self) -> LogLevel;
@@ -36,9 +80,55 @@ pub trait Record {
     /// Line number.
     fn line(&self) -> u32;
 
+    /// Sequence number.
+    ///
+    /// The sequence numbers of the records are monotonically increasing across all threads.
+    ///
+    /// Defaults to zero.
+    fn seq(&self) -> u64 {
+        0
+    }
+
     /// Timestamp.
     fn ts(&self) -> time::Timespec;
 
+    /// Monotonic timestamp.
+    ///
+    /// Unlike the wall-clock [timestamp](#tymethod.ts) it's not affected by the system clock adjustments.
+    ///
+    /// Defaults to the time of the call.
+    fn ts_mono(&self) -> Instant {
+        Instant::now()
+    }
+
+    /// Time elapsed since [init](../fn.init.html).
+    ///
+    /// Defaults to zero.
+    fn elapsed(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    /// Time elapsed since the previous record produced by the same thread.
+    ///
+    /// It's zero for the first record of the thread and by default.
+    fn delta(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    /// Identifier of the thread that produced the record.
+    ///
+    /// Defaults to zero.
+    fn thread_id(&self) -> usize {
+        0
+    }
+
+    /// Name of the thread that produced the record if any.
+    ///
+    /// Defaults to none.
+    fn thread_name(&self) -> Option<&str> {
+        None
+    }
+
     /// Returns user log message as a formatted string.
     fn msg(&self) -> Arc<String>;
 
@@ -47,6 +137,28 @@ pub trait Record {
 
     /// Returns timestamp in UTC.
     fn ts_utc(&self) -> Arc<DateTime<UTC>>;
+
+    /// Returns timestamp in the local time zone.
+    ///
+    /// Defaults to the [UTC timestamp](#tymethod.ts_utc) converted to the local time zone.
+    fn ts_local(&self) -> Arc<DateTime<Local>> {
+        Arc::new(self.ts_utc().with_timezone(&Local))
+    }
+
+    /// Returns timestamp formatted according to the
+    /// [timestamp style](../formatters/timestamp/index.html).
+    ///
+    /// Defaults to the [UTC timestamp](#tymethod.ts_utc) formatted on every call.
+    fn ts_formatted(&self) -> Arc<String> {
+        Arc::new(timestamp::get_style().format(&self.ts_utc()))
+    }
+
+    /// Returns the formatter that produces the [formatted](#tymethod.formatted) record
+    /// so that a copy of the record might be formatted on demand.
+    #[doc(hidden)]
+    fn formatter(&self) -> Option<Arc<Formatter>> {
+        None
+    }
 }
 
 #[doc(hidden)]
@@ -0,0 +1,207 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Redaction of secrets and personal data in the log records.
+//!
+//! The redaction rules mask the matching parts of the log messages
+//! before the log records are seen by the [filters](../filters/index.html) and handlers.
+//! Both [msg](../record/trait.Record.html#tymethod.msg) and
+//! [formatted](../record/trait.Record.html#tymethod.formatted) of the record
+//! contain the masked message.
+//!
+//! The rules are applied in the order of registration.
+//!
+//! Unlike the handlers and formatters the redaction rules survive
+//! the reconfiguration with the [configure](../fn.configure.html) function.
+//!
+//! # Example
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate woodpecker;
+//! use woodpecker as wp;
+//!
+//! use wp::redact;
+//!
+//! use std::sync::{Arc, Mutex};
+//! use std::ops::Deref;
+//!
+//! fn main() {
+//!     wp_init!();
+//!
+//!     wp_register_redaction!(redact::emails());
+//!     wp_register_redaction!(redact::bearer_tokens());
+//!     wp_register_redaction!(redact::Rule::new(r"session=\w+").unwrap().mask("session=***"));
+//!
+//!     let out = Arc::new(Mutex::new(String::new()));
+//!     {
+//!         let out = out.clone();
+//!         wp_register_handler!(Box::new(move |record: &dyn wp::Record| {
+//!             out.lock().unwrap().push_str(record.msg().deref());
+//!         }));
+//!     }
+//!
+//!     warn!("{} logged in with Bearer abc.def, session=1234", "joe@example.com");
+//!
+//!     if cfg!(feature = "test-thread-log") {
+//!         wp::sync();
+//!     }
+//!
+//!     assert_eq!(*out.lock().unwrap(), "[REDACTED] logged in with Bearer [REDACTED], session=***");
+//! }
+//!
+//! ```
+
+extern crate regex;
+use self::regex::{Captures, Regex};
+
+use std::sync::Arc;
+
+const MASK: &str = "[REDACTED]";
+
+/// Redaction rule.
+///
+/// Replaces the parts of the message that match the regex with the mask.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    regex: Regex,
+    mask: String,
+    // Validates the match, the invalid matches are kept as is.
+    check: Option<fn(&str) -> bool>,
+}
+
+impl Rule {
+    /// Creates the rule which replaces the matches of the regex with `[REDACTED]`.
+    pub fn new(regex: &str) -> Result<Self, String> {
+        let regex = Regex::new(regex).map_err(|err| err.to_string())?;
+        Ok(Rule {
+            regex,
+            mask: MASK.to_string(),
+            check: None,
+        })
+    }
+
+    /// Sets the mask.
+    ///
+    /// The mask might refer to the capture groups of the regex, e.g. `${1}***`.
+    pub fn mask(mut self, mask: &str) -> Self {
+        self.mask = mask.to_string();
+        self
+    }
+}
+
+/// Masks the bearer tokens, e.g. `Authorization: Bearer <token>`.
+///
+/// The `Bearer` keyword is kept.
+pub fn bearer_tokens() -> Rule {
+    Rule::new(r"(?i)\b(bearer\s+)[A-Za-z0-9\-._~+/]+=*").unwrap().mask("${1}[REDACTED]")
+}
+
+/// Masks the email addresses.
+pub fn emails() -> Rule {
+    Rule::new(r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}").unwrap()
+}
+
+/// Masks the credit card numbers.
+///
+/// These are the sequences of 13 to 19 digits optionally grouped by spaces or dashes
+/// which pass the Luhn checksum, so that e.g. the timestamps are not masked.
+pub fn credit_cards() -> Rule {
+    let mut rule = Rule::new(r"\b\d(?:[ \-]?\d){12,18}\b").unwrap();
+    rule.check = Some(luhn);
+    rule
+}
+
+// Validates the checksum of the card number ignoring the separators.
+fn luhn(number: &str) -> bool {
+    let sum: u32 = number.bytes()
+        .filter(u8::is_ascii_digit)
+        .rev()
+        .enumerate()
+        .map(|(idx, digit)| {
+            let digit = u32::from(digit - b'0');
+            match idx % 2 {
+                0 => digit,
+                _ if digit > 4 => digit * 2 - 9,
+                _ => digit * 2,
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Applies the rules to the message.
+///
+/// Returns `None` if nothing is masked.
+pub(crate) fn apply(rules: &[Rule], msg: &Arc<String>) -> Option<Arc<String>> {
+    let mut out: Option<String> = None;
+    for rule in rules {
+        let masked = {
+            let msg = out.as_ref().map_or(msg.as_str(), |out| out.as_str());
+            if !rule.regex.is_match(msg) {
+                continue;
+            }
+            match rule.check {
+                None => rule.regex.replace_all(msg, rule.mask.as_str()).into_owned(),
+                Some(check) => {
+                    let mut masked = false;
+                    let out = rule.regex.replace_all(msg, |caps: &Captures| {
+                        if !check(&caps[0]) {
+                            return caps[0].to_string();
+                        }
+                        masked = true;
+                        let mut dst = String::new();
+                        caps.expand(&rule.mask, &mut dst);
+                        dst
+                    }).into_owned();
+                    if !masked {
+                        continue;
+                    }
+                    out
+                },
+            }
+        };
+        out = Some(masked);
+    }
+    out.map(Arc::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact(rules: &[Rule], msg: &str) -> String {
+        let msg = Arc::new(msg.to_string());
+        apply(rules, &msg).unwrap_or(msg).to_string()
+    }
+
+    #[test]
+    fn test_redact() {
+        let rules = [emails(), bearer_tokens(), credit_cards()];
+        assert_eq!(redact(&rules, "no secrets"), "no secrets");
+        assert!(apply(&rules, &Arc::new("no secrets".to_string())).is_none());
+
+        assert_eq!(redact(&rules, "mail to john.doe+x@mail.example.org now"), "mail to [REDACTED] now");
+        assert_eq!(redact(&rules, "Authorization: bearer eyJhbGciOi.J9-x_y=="), "Authorization: bearer [REDACTED]");
+        assert_eq!(redact(&rules, "card 4111 1111 1111 1111, exp 12/30"), "card [REDACTED], exp 12/30");
+        assert_eq!(redact(&rules, "card 4111-1111-1111-1111"), "card [REDACTED]");
+        assert_eq!(redact(&rules, "order 123456"), "order 123456");
+        assert_eq!(redact(&rules, "at 1718035200123 card 4111111111111111"), "at 1718035200123 card [REDACTED]");
+        assert!(apply(&rules, &Arc::new("card 4111 1111 1111 1112".to_string())).is_none());
+
+        let rules = [Rule::new(r"(password=)\S+").unwrap().mask("${1}***")];
+        assert_eq!(redact(&rules, "user=joe password=secret"), "user=joe password=***");
+        assert!(Rule::new("(").is_err());
+    }
+}
@@ -27,12 +27,50 @@
 //! ```
 //!
 //! The level must be a valid logging [level](levels/enum.LogLevel.html) level in the range
-//! from [TRACE](levels/enum.LogLevel.html) to [CRITICAL](levels/enum.LogLevel.html).
+//! from [TRACE](levels/enum.LogLevel.html) to [CRITICAL](levels/enum.LogLevel.html),
+//! `OFF` to silence the module completely or `ALL` which is the same as `TRACE`.
 //!
 //! If `level` doesn't match any known logging level it's treated as a module path.
 //!
 //! In this case if `level` is not specified the log level is set to [TRACE](levels/enum.LogLevel.html).
 //!
+//! ## Line ranges
+//!
+//! The file path might be followed by a range of lines:
+//!
+//! ```ignore
+//! myapp::db@src/db.rs:120-180=debug,src/db.rs:*=trace
+//! ```
+//!
+//! The range is one of:
+//!
+//! * `<from>-<to>`, e.g. `120-180`;
+//! * `<from>-` for the lines from `from` up to the end of the file;
+//! * `<line>` for a single line;
+//! * `*` for all lines of the file.
+//!
+//! If the path doesn't contain the module part it applies to the file in any module,
+//! i.e. `src/db.rs` is the same as `**@src/db.rs`.
+//!
+//! Several ranges of the same file are defined with several rules, e.g.
+//! `src/db.rs:10-20=debug,src/db.rs:40-50=debug`.
+//!
+//! ## Message filter
+//!
+//! Same as in env_logger the spec might be followed by `/` and a regex:
+//!
+//! ```ignore
+//! info,myapp::db=debug/timeout|retry
+//! ```
+//!
+//! Only the log records whose message matches the regex are emitted.
+//! The filter is inverted if the regex starts with `!`, e.g. `info/!heartbeat`
+//! drops the records whose message matches `heartbeat`.
+//!
+//! The filter starts at the first `/` that follows a log level or
+//! at the very beginning of the spec and extends up to the end of the spec,
+//! so the regex might contain `,` and `=`.
+//!
 //! # Extended JSON spec
 //!
 //! The JSON logging spec allows to specify a fine grained logging settings.
@@ -56,8 +94,13 @@
 //!             "path": "<path to the module>",
 //!             "lines": [[<from>, <to>], ...]
 //!         },
+//!         {
+//!             "path": "<path to the module>",
+//!             "message": "<regex>"
+//!         },
 //!         ...
-//!     ]
+//!     ],
+//!     "message": "<regex>"
 //! }
 //! ```
 //!
@@ -68,8 +111,107 @@
 //!
 //! If module `level` is not specified then it defaults to [TRACE](levels/enum.LogLevel.html).
 //!
+//! Besides the name the level might be given by its numeric value,
+//! e.g. a custom level which is not [registered](levels/fn.register.html) yet.
+//! The value must be in the range accepted by the registration.
+//!
 //! In case the ranges of lines is omitted the logging for the whole file is defined.
 //!
+//! The top-level `message` filter applies to all log records
+//! while the `message` filter of the module applies to the records of the module
+//! in addition to the top-level one.
+//! Same as the module log level, the filter of the most specific rule applies,
+//! i.e. the filter isn't inherited by the children which have their own rules.
+//! The module filter can't be combined with the `lines`.
+//!
+//! # Handlers and formatters
+//!
+//! The JSON spec might also define the whole logging configuration
+//! when applied with [configure](../fn.configure.html) or
+//! [Logger::configure](../struct.Logger.html#method.configure).
+//!
+//! ```json
+//! {
+//!     "level": "info",
+//!     "formatter": "<name of the formatter>",
+//!     "formatters": {
+//!         "<name of the formatter>": "<pattern>",
+//!         ...
+//!     },
+//!     "handlers": [
+//!         {
+//!             "name": "<name of the handler>",
+//!             "type": "stdout" | "stderr" | "file" | "rotating_file",
+//!             "path": "<path to the log file>",
+//!             "count": <number of the rotated files>,
+//!             "size": <max size of the log file>,
+//!             "level": "<handler logging level>",
+//!             "formatter": "<name of the formatter>"
+//!         },
+//!         ...
+//!     ],
+//!     "modules": [
+//!         {
+//!             "path": "<path to the module>",
+//!             "level": "<module logging level>",
+//!             "handlers": ["<name of the handler>", ...]
+//!         },
+//!         ...
+//!     ]
+//! }
+//! ```
+//!
+//! The formatters are defined with the [patterns](../formatters/pattern/index.html).
+//! The `default` formatter is predefined and refers to the
+//! [default](../formatters/default/fn.formatter.html) formatter.
+//! The top-level `formatter` is used by the handlers which don't specify their own one.
+//!
+//! The `path` field is mandatory for the `file` and `rotating_file` handlers
+//! while the `count` and `size` are mandatory for the `rotating_file` handler only.
+//!
+//! The `level` of the handler filters out the records below it
+//! in addition to the module and global log levels.
+//!
+//! The log records of a module which lists the `handlers` are pushed only into these handlers.
+//! Same as for the log levels the route of the module applies to its children
+//! and the most specific route wins.
+//! The routes might refer to the files as well, e.g. `foo@src/db.rs`.
+//! The log records of the modules which aren't routed are pushed into all handlers.
+//!
+//! # TOML spec
+//!
+//! The same settings might be defined in TOML, e.g.:
+//!
+//! ```toml
+//! level = "info"
+//!
+//! [[modules]]
+//! path = "myapp::db"
+//! level = "debug"
+//! lines = [[10, 100]]
+//! ```
+//!
+//! The TOML spec is accepted by the [Loader](../loader/struct.Loader.html).
+//!
+//! # Path patterns
+//!
+//! In both specs the module path might be a glob pattern or an anchored regex.
+//!
+//! The path is a regex if it starts with `^`, e.g. `^myapp::(db|cache)::`.
+//!
+//! Otherwise it's a glob pattern if it contains `*` or `?`:
+//!
+//! * `*` matches any part of a single path segment, e.g. `*::db::*`;
+//! * `?` matches any single character within a path segment;
+//! * `**` matches any number of path segments, e.g. `myapp::**::cache`.
+//!
+//! Same as for the plain paths, a pattern that matches a module also matches its children.
+//!
+//! If several rules match a path, the one that matches the longer part of the path wins.
+//! Among the patterns that match the same path the last defined one wins.
+//!
+//! Note that the regexes containing `,` or `=` can be defined only in the JSON spec.
+//!
 //! See documentation for the [wp_set_level](../macro.wp_set_level.html)
 //! for examples.
 
@@ -77,12 +219,21 @@
 extern crate serde_json;
 use self::serde_json::Value;
 
-use std;
+extern crate toml;
+
+extern crate regex;
+use self::regex::Regex;
+
 use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+use std::collections::HashMap;
 
 use levels::LogLevel;
 use line_range;
-use line_range::Range;
+use line_range::{Range, LineRangeBound};
+use path_pattern::{self, PathPattern};
+use formatters::pattern;
 
 #[doc(hidden)]
 #[derive(PartialEq, PartialOrd, Clone, Debug)]
@@ -90,38 +241,123 @@ pub struct Module {
     pub path: String,
     pub level: LogLevel,
     pub lranges: Vec<Range>,
+    pub handlers: Vec<String>,
+    pub filter: Option<Filter>,
 }
 
 impl Module {
-    fn with_level(path: &str, level: LogLevel) -> Self {
+    #[doc(hidden)]
+    pub fn with_level(path: &str, level: LogLevel) -> Self {
         Module {
             path: path.to_string(),
-            level: level,
+            level,
             lranges: Vec::new(),
+            handlers: Vec::new(),
+            filter: None,
         }
     }
 
-    fn with_lranges(path: &str, level: LogLevel, lranges: Vec<Range>) -> Self {
+    #[doc(hidden)]
+    pub fn with_lranges(path: &str, level: LogLevel, lranges: Vec<Range>) -> Self {
         Module {
             path: path.to_string(),
-            level: level,
-            lranges: lranges,
+            level,
+            lranges,
+            handlers: Vec::new(),
+            filter: None,
         }
     }
 }
 
+#[doc(hidden)]
+#[derive(Clone, Debug)]
+pub struct Filter {
+    regex: Regex,
+    invert: bool,
+}
+
+impl Filter {
+    #[doc(hidden)]
+    pub fn new(filter: &str) -> Result<Self, ParseError> {
+        let (invert, regex) = match filter.strip_prefix('!') {
+            Some(regex) => (true, regex),
+            None => (false, filter),
+        };
+        let regex = Regex::new(regex)
+            .map_err(|_| ParseError::new(ErrorKind::Filter).with(filter))?;
+        Ok(Filter {
+            regex,
+            invert,
+        })
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn accepts(&self, msg: &str) -> bool {
+        self.regex.is_match(msg) != self.invert
+    }
+}
+
+impl PartialEq for Filter {
+    fn eq(&self, other: &Filter) -> bool {
+        self.invert == other.invert && self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl PartialOrd for Filter {
+    fn partial_cmp(&self, other: &Filter) -> Option<Ordering> {
+        (self.invert, self.regex.as_str()).partial_cmp(&(other.invert, other.regex.as_str()))
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.invert {
+            write!(f, "!")?;
+        }
+        write!(f, "{}", self.regex.as_str())
+    }
+}
+
+#[doc(hidden)]
+#[derive(PartialEq, PartialOrd, Clone, Debug)]
+pub enum HandlerKind {
+    Stdout,
+    Stderr,
+    File(String),
+    RotatingFile(String, usize, u64),
+}
+
+#[doc(hidden)]
+#[derive(PartialEq, PartialOrd, Clone, Debug)]
+pub struct Handler {
+    pub name: String,
+    pub kind: HandlerKind,
+    pub level: Option<LogLevel>,
+    pub formatter: Option<String>,
+}
+
 #[doc(hidden)]
 #[derive(PartialEq, PartialOrd, Clone, Debug)]
 pub struct Root {
     pub level: Option<LogLevel>,
     pub modules: Vec<Module>,
+    pub formatter: Option<String>,
+    pub formatters: Vec<(String, String)>,
+    pub handlers: Vec<Handler>,
+    pub filter: Option<Filter>,
 }
 
 impl Root {
-    fn new() -> Self {
+    #[doc(hidden)]
+    pub fn new() -> Self {
         Root {
             level: None,
             modules: Vec::new(),
+            formatter: None,
+            formatters: Vec::new(),
+            handlers: Vec::new(),
+            filter: None,
         }
     }
 
@@ -129,10 +365,17 @@ impl Root {
     fn with_level(level: LogLevel) -> Self {
         Root {
             level: Some(level),
-            modules: Vec::new(),
+            ..Root::new()
         }
     }
 
+    #[doc(hidden)]
+    pub fn pattern(&self, formatter: &str) -> Option<&str> {
+        self.formatters.iter()
+            .find(|(name, _)| name == formatter)
+            .map(|(_, pattern)| pattern.as_str())
+    }
+
     #[cfg(test)]
     fn module(mut self, module: Module) -> Self {
         self.modules.push(module);
@@ -157,148 +400,511 @@ pub enum JsonError {
     LineRange,
     /// Line level intersection with different log levels.
     Intersection,
+    /// The handler definition is invalid or the module refers to an unknown handler.
+    Handler,
+    /// The formatter definition is invalid or refers to an unknown formatter.
+    Formatter,
+    /// The message filter is invalid.
+    Filter,
 }
 
-/// Generic log spec parse failure.
+/// The kind of log spec parse failure.
 #[derive(PartialEq, PartialOrd, Clone, Debug)]
-pub enum ParseError {
+pub enum ErrorKind {
     /// Invalid spec.
     Spec,
     /// Invalid log level.
     LogLevel,
     /// JSON parse error.
     Json(JsonError),
+    /// Invalid glob pattern or regex in the module path.
+    Pattern,
+    /// Invalid TOML string.
+    Toml,
+    /// Invalid line range.
+    LineRange,
+    /// Invalid regex of the message filter.
+    Filter,
+    /// Intersecting line ranges with different log levels.
+    Intersection,
+}
+
+impl ErrorKind {
+    fn describe(&self) -> &'static str {
+        match *self {
+            ErrorKind::Spec => "invalid spec",
+            ErrorKind::LogLevel => "invalid log level",
+            ErrorKind::Json(JsonError::Json) => "invalid JSON",
+            ErrorKind::Json(JsonError::Root) => "invalid spec root",
+            ErrorKind::Json(JsonError::Module) => "invalid module",
+            ErrorKind::Json(JsonError::RootLogLevel) => "invalid log level",
+            ErrorKind::Json(JsonError::ModuleLogLevel) => "invalid log level of the module",
+            ErrorKind::Json(JsonError::LineRange) => "invalid line range",
+            ErrorKind::Json(JsonError::Intersection) => "intersecting line ranges with different log levels",
+            ErrorKind::Json(JsonError::Handler) => "invalid handler",
+            ErrorKind::Json(JsonError::Formatter) => "invalid formatter",
+            ErrorKind::Json(JsonError::Filter) => "invalid message filter",
+            ErrorKind::Pattern => "invalid path pattern",
+            ErrorKind::Toml => "invalid TOML",
+            ErrorKind::LineRange => "invalid line range",
+            ErrorKind::Filter => "invalid message filter",
+            ErrorKind::Intersection => "intersecting line ranges with different log levels",
+        }
+    }
+}
+
+/// The position of the log spec parse failure.
+#[derive(PartialEq, PartialOrd, Clone, Debug)]
+pub enum Position {
+    /// Index of the comma separated token of the env_logger spec, starting from 0.
+    Token(usize),
+    /// JSON pointer to the offending value, e.g. `/modules/1/level`.
+    Pointer(String),
+    /// Line and column of the JSON or TOML syntax error, starting from 1.
+    Line(usize, usize),
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Position::Token(index) => write!(f, "token {}", index),
+            Position::Pointer(ref pointer) => write!(f, "{}", pointer),
+            Position::Line(line, column) => write!(f, "line {}, column {}", line, column),
+        }
+    }
+}
+
+/// Log spec parse failure.
+///
+/// # Example
+///
+/// ```rust
+/// use woodpecker::spec::{self, ErrorKind, Position};
+///
+/// let err = spec::parse("info,foo=bar").err().unwrap();
+/// assert_eq!(err.kind, ErrorKind::LogLevel);
+/// assert_eq!(err.position, Some(Position::Token(1)));
+/// assert_eq!(err.value, Some("bar".to_string()));
+/// assert_eq!(err.to_string(), r#"invalid log level "bar" at token 1"#);
+/// ```
+#[derive(PartialEq, PartialOrd, Clone, Debug)]
+pub struct ParseError {
+    /// The kind of failure.
+    pub kind: ErrorKind,
+    /// The position of the offending value in the spec if known.
+    pub position: Option<Position>,
+    /// The offending value or the description of the syntax error.
+    pub value: Option<String>,
+}
+
+impl ParseError {
+    fn new(kind: ErrorKind) -> Self {
+        ParseError {
+            kind,
+            position: None,
+            value: None,
+        }
+    }
+
+    fn at(mut self, position: Position) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    fn with<T: Into<String>>(mut self, value: T) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    fn json(kind: JsonError, pointer: &str, value: Option<&Value>) -> Self {
+        let err = ParseError::new(ErrorKind::Json(kind))
+            .at(Position::Pointer(pointer.to_string()));
+        match value {
+            Some(Value::String(value)) => err.with(value.clone()),
+            Some(value) => err.with(value.to_string()),
+            None => err,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind.describe())?;
+        let syntax = self.kind == ErrorKind::Json(JsonError::Json) || self.kind == ErrorKind::Toml;
+        if let (false, Some(ref value)) = (syntax, &self.value) {
+            write!(f, " {:?}", value)?;
+        }
+        if let Some(ref position) = self.position {
+            write!(f, " at {}", position)?;
+        }
+        if let (true, Some(ref value)) = (syntax, &self.value) {
+            write!(f, ": {}", value)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ParseError {}
+
+fn check_path(path: &str) -> Result<(), ParseError> {
+    if path_pattern::is_pattern(path) && PathPattern::new(path).is_err() {
+        return Err(ParseError::new(ErrorKind::Pattern).with(path));
+    }
+    Ok(())
+}
+
+// Strips the position from the description of the syntax error.
+fn syntax_error(err: &str) -> String {
+    match err.rfind(" at line ") {
+        Some(idx) => err[..idx].to_string(),
+        None => err.to_string(),
+    }
 }
 
 fn parse_json(json: &str) -> Result<Root, ParseError> {
     let spec: Value = serde_json::from_str(json)
-        .or(Err(ParseError::Json(JsonError::Json)))?;
-    let spec = spec.as_object()
-        .ok_or(ParseError::Json(JsonError::Root))?;
-    if spec.is_empty() {
-        return Err(ParseError::Json(JsonError::Root));
+        .map_err(|err| {
+            ParseError::new(ErrorKind::Json(JsonError::Json))
+                .at(Position::Line(err.line(), err.column()))
+                .with(syntax_error(&err.to_string()))
+        })?;
+    parse_value(&spec)
+}
+
+fn parse_level(kind: JsonError, pointer: &str, level: &Value) -> Result<LogLevel, ParseError> {
+    let parsed = match *level {
+        Value::String(ref level) => level.parse().ok(),
+        // The same range as of the registered custom levels.
+        Value::Number(ref value) => value.as_i64()
+            .filter(|&value| value > LogLevel::UNSUPPORTED.value() as i64 && value < LogLevel::LOG.value() as i64)
+            .map(|value| LogLevel::from_value(value as isize)),
+        _ => None,
+    };
+    parsed.ok_or_else(|| ParseError::json(kind, pointer, Some(level)))
+}
+
+// The unregistered custom log levels have no name.
+fn level_value(level: LogLevel) -> Value {
+    match level {
+        LogLevel::CUSTOM(value) if matches!(LogLevel::from(value), LogLevel::UNSUPPORTED) => {
+            Value::from(value as i64)
+        },
+        level => Value::from(level.to_string()),
     }
+}
+
+fn parse_filter(pointer: &str, filter: &Value) -> Result<Filter, ParseError> {
+    filter.as_str()
+        .and_then(|filter| Filter::new(filter).ok())
+        .ok_or_else(|| ParseError::json(JsonError::Filter, pointer, Some(filter)))
+}
+
+fn parse_value(spec: &Value) -> Result<Root, ParseError> {
+    let spec = match spec.as_object() {
+        Some(spec) if !spec.is_empty() => spec,
+        _ => return Err(ParseError::json(JsonError::Root, "", Some(spec))),
+    };
 
     let mut root = Root::new();
 
     if let Some(level) = spec.get("level") {
-        root.level = Some(
-            level.as_str()
-                .ok_or(ParseError::Json(JsonError::RootLogLevel))?.parse()
-                .or(Err(ParseError::Json(JsonError::RootLogLevel)))?
-        );
+        root.level = Some(parse_level(JsonError::RootLogLevel, "/level", level)?);
     }
 
-    if let Some(modules) = spec.get("modules") {
-        let modules = modules.as_array()
-            .ok_or(ParseError::Json(JsonError::Module))?;
-        if modules.is_empty() {
-            return Err(ParseError::Json(JsonError::Module));
-        }
-        for module in modules {
-            let module = module.as_object()
-                .ok_or(ParseError::Json(JsonError::Module))?;
-            let path = module.get("path")
-                .ok_or(ParseError::Json(JsonError::Module))?.as_str()
-                .ok_or(ParseError::Json(JsonError::Module))?;
-            let level = if let Some(level) = module.get("level") {
-                level.as_str()
-                    .ok_or(ParseError::Json(JsonError::ModuleLogLevel))?.parse()
-                    .or(Err(ParseError::Json(JsonError::ModuleLogLevel)))?
-            } else {
-                LogLevel::TRACE
-            };
+    if let Some(filter) = spec.get("message") {
+        root.filter = Some(parse_filter("/message", filter)?);
+    }
 
-            let module = if let Some(lines) = module.get("lines") {
-                let lines = lines.as_array()
-                    .ok_or(ParseError::Json(JsonError::LineRange))?;
-                let mut lranges = Vec::new();
-                if lines.is_empty() {
-                    return Err(ParseError::Json(JsonError::LineRange));
-                }
-                for line in lines {
-                    let line = line.as_array()
-                        .ok_or(ParseError::Json(JsonError::LineRange))?;
-                    if line.len() != 2 {
-                        return Err(ParseError::Json(JsonError::LineRange));
-                    }
-                    let from = line[0].as_u64()
-                        .ok_or(ParseError::Json(JsonError::LineRange))?;
-                    if from > std::u32::MAX as u64 {
-                        return Err(ParseError::Json(JsonError::LineRange))
-                    }
-                    let to = line[1].as_u64()
-                        .ok_or(ParseError::Json(JsonError::LineRange))?;
-                    if to > std::u32::MAX as u64 {
-                        return Err(ParseError::Json(JsonError::LineRange))
-                    }
-                    if from > to {
-                        return Err(ParseError::Json(JsonError::LineRange))
-                    }
-                    lranges.push(
-                        Range::new(from as u32, to as u32)
-                            .or(Err(ParseError::Json(JsonError::LineRange)))?
-                    );
-                }
-                Module::with_lranges(path, level, lranges)
-            } else {
-                Module::with_level(path, level)
+    if let Some(formatters) = spec.get("formatters") {
+        let formatters = formatters.as_object()
+            .ok_or_else(|| ParseError::json(JsonError::Formatter, "/formatters", Some(formatters)))?;
+        for (name, fmt) in formatters {
+            let pointer = format!("/formatters/{}", name);
+            let pattern = match fmt.as_str() {
+                Some(pattern) if name != "default" && pattern::formatter(pattern).is_ok() => pattern,
+                _ => return Err(ParseError::json(JsonError::Formatter, &pointer, Some(fmt))),
             };
-            root.modules.push(module);
+            root.formatters.push((name.clone(), pattern.to_string()));
+        }
+    }
+
+    if let Some(formatter) = spec.get("formatter") {
+        root.formatter = Some(check_formatter(&root, "/formatter", formatter)?);
+    }
+
+    if let Some(handlers) = spec.get("handlers") {
+        let handlers = handlers.as_array()
+            .ok_or_else(|| ParseError::json(JsonError::Handler, "/handlers", Some(handlers)))?;
+        for (idx, handler) in handlers.iter().enumerate() {
+            let pointer = format!("/handlers/{}", idx);
+            let handler = parse_handler(&root, &pointer, handler)?;
+            if root.handlers.iter().any(|h| h.name == handler.name) {
+                return Err(ParseError::json(JsonError::Handler, &format!("{}/name", pointer), None)
+                           .with(handler.name));
+            }
+            root.handlers.push(handler);
+        }
+    }
+
+    if let Some(modules) = spec.get("modules") {
+        let modules = match modules.as_array() {
+            Some(modules) if !modules.is_empty() => modules,
+            _ => return Err(ParseError::json(JsonError::Module, "/modules", Some(modules))),
+        };
+        for (idx, module) in modules.iter().enumerate() {
+            let pointer = format!("/modules/{}", idx);
+            root.modules.push(parse_module(&root, &pointer, module)?);
+            if intersects(&root) {
+                return Err(ParseError::json(JsonError::Intersection, &format!("{}/lines", pointer),
+                                            module.get("lines")));
+            }
         }
     }
 
     Ok(root)
 }
 
-fn parse_token(root: &mut Root, token: &str) -> Result<(), ParseError> {
-    if token.is_empty() {
-        return Err(ParseError::Spec);
+fn parse_module(root: &Root, pointer: &str, module: &Value) -> Result<Module, ParseError> {
+    let object = module.as_object()
+        .ok_or_else(|| ParseError::json(JsonError::Module, pointer, Some(module)))?;
+
+    let path_pointer = format!("{}/path", pointer);
+    let path = match object.get("path") {
+        Some(path) => {
+            path.as_str()
+                .ok_or_else(|| ParseError::json(JsonError::Module, &path_pointer, Some(path)))?
+        },
+        None => return Err(ParseError::json(JsonError::Module, &path_pointer, None)),
+    };
+    check_path(path).map_err(|err| err.at(Position::Pointer(path_pointer)))?;
+
+    let level = if let Some(level) = object.get("level") {
+        parse_level(JsonError::ModuleLogLevel, &format!("{}/level", pointer), level)?
+    } else {
+        LogLevel::TRACE
+    };
+
+    let mut module = if let Some(lines) = object.get("lines") {
+        let lines_pointer = format!("{}/lines", pointer);
+        let lines = match lines.as_array() {
+            Some(lines) if !lines.is_empty() => lines,
+            _ => return Err(ParseError::json(JsonError::LineRange, &lines_pointer, Some(lines))),
+        };
+        let mut lranges = Vec::new();
+        for (idx, line) in lines.iter().enumerate() {
+            let err = || ParseError::json(JsonError::LineRange, &format!("{}/{}", lines_pointer, idx), Some(line));
+            let bounds = match line.as_array() {
+                Some(bounds) if bounds.len() == 2 => bounds,
+                _ => return Err(err()),
+            };
+            let (from, to) = match (bounds[0].as_u64(), bounds[1].as_u64()) {
+                (Some(from), Some(to)) if to <= u32::MAX as u64 && from <= to => (from, to),
+                _ => return Err(err()),
+            };
+            lranges.push(Range::new(from as u32, to as u32).map_err(|_| err())?);
+        }
+        Module::with_lranges(path, level, lranges)
+    } else {
+        Module::with_level(path, level)
+    };
+
+    if let Some(handlers) = object.get("handlers") {
+        let handlers_pointer = format!("{}/handlers", pointer);
+        let handlers = match handlers.as_array() {
+            Some(handlers) if !handlers.is_empty() => handlers,
+            _ => return Err(ParseError::json(JsonError::Handler, &handlers_pointer, Some(handlers))),
+        };
+        for (idx, name) in handlers.iter().enumerate() {
+            match name.as_str() {
+                Some(name) if root.handlers.iter().any(|h| h.name == name) => {
+                    module.handlers.push(name.to_string());
+                },
+                _ => {
+                    let pointer = format!("{}/{}", handlers_pointer, idx);
+                    return Err(ParseError::json(JsonError::Handler, &pointer, Some(name)));
+                },
+            }
+        }
     }
 
-    let mut kv = token.split('=');
-    let (k, v) = (kv.next(), kv.next());
+    if let Some(filter) = object.get("message") {
+        let filter_pointer = format!("{}/message", pointer);
+        if !module.lranges.is_empty() {
+            return Err(ParseError::json(JsonError::Filter, &filter_pointer, Some(filter)));
+        }
+        module.filter = Some(parse_filter(&filter_pointer, filter)?);
+    }
 
-    if kv.next().is_some() {
-        return Err(ParseError::Spec);
+    Ok(module)
+}
+
+fn check_formatter(root: &Root, pointer: &str, name: &Value) -> Result<String, ParseError> {
+    match name.as_str() {
+        Some(fmt) if fmt == "default" || root.pattern(fmt).is_some() => Ok(fmt.to_string()),
+        _ => Err(ParseError::json(JsonError::Formatter, pointer, Some(name))),
     }
+}
+
+fn parse_handler(root: &Root, pointer: &str, handler: &Value) -> Result<Handler, ParseError> {
+    let object = handler.as_object()
+        .ok_or_else(|| ParseError::json(JsonError::Handler, pointer, Some(handler)))?;
+    let field = |name: &str| {
+        let pointer = format!("{}/{}", pointer, name);
+        match object.get(name) {
+            Some(value) => Ok((pointer, value)),
+            None => Err(ParseError::json(JsonError::Handler, &pointer, None)),
+        }
+    };
+    let string = |name: &str| {
+        let (pointer, value) = field(name)?;
+        value.as_str().ok_or_else(|| ParseError::json(JsonError::Handler, &pointer, Some(value)))
+    };
+    let number = |name: &str| {
+        let (pointer, value) = field(name)?;
+        value.as_u64().ok_or_else(|| ParseError::json(JsonError::Handler, &pointer, Some(value)))
+    };
+
+    let name = string("name")?;
+    let kind = match string("type")? {
+        "stdout" => HandlerKind::Stdout,
+        "stderr" => HandlerKind::Stderr,
+        "file" => HandlerKind::File(string("path")?.to_string()),
+        "rotating_file" => HandlerKind::RotatingFile(
+            string("path")?.to_string(),
+            number("count")? as usize,
+            number("size")?,
+        ),
+        kind => {
+            return Err(ParseError::json(JsonError::Handler, &format!("{}/type", pointer), None)
+                       .with(kind));
+        },
+    };
+
+    let level = if let Some(level) = object.get("level") {
+        Some(parse_level(JsonError::Handler, &format!("{}/level", pointer), level)?)
+    } else {
+        None
+    };
+
+    let formatter = if let Some(formatter) = object.get("formatter") {
+        Some(check_formatter(root, &format!("{}/formatter", pointer), formatter)?)
+    } else {
+        None
+    };
+
+    Ok(Handler {
+        name: name.to_string(),
+        kind,
+        level,
+        formatter,
+    })
+}
 
-    let k = k.unwrap().trim();
-    if k.is_empty() {
-        return Err(ParseError::Spec);
+// Splits the path into the file path and the range of lines if any.
+fn parse_lines(path: &str) -> Result<(String, Option<Range>), ParseError> {
+    let idx = match path.rfind(':') {
+        Some(idx) if !path[..idx].ends_with(':') => idx,
+        _ => return Ok((path.to_string(), None)),
+    };
+    let (file, lines) = (&path[..idx], &path[idx + 1..]);
+    if file.is_empty() {
+        return Err(ParseError::new(ErrorKind::Spec).with(path));
     }
+    let file = if file.contains(wp_separator!()) {
+        file.to_string()
+    } else {
+        format!("**{}{}", wp_separator!(), file)
+    };
 
-    if v.is_none() {
-        // `k` is either global log level or path for which log level is `TRACE`
-        if let Ok(level) = k.to_uppercase().parse() {
-            root.level = Some(level);
+    let err = || ParseError::new(ErrorKind::LineRange).with(lines);
+    let line = |line: &str| line.parse::<u32>().map_err(|_| err());
+    let (from, to) = if lines == "*" {
+        (LineRangeBound::BOF.into(), LineRangeBound::EOF.into())
+    } else if let Some(idx) = lines.find('-') {
+        let (from, to) = (&lines[..idx], &lines[idx + 1..]);
+        let to = if to.is_empty() {
+            LineRangeBound::EOF.into()
         } else {
-            root.modules.push(Module::with_level(k, LogLevel::TRACE));
-        }
+            line(to)?
+        };
+        (line(from)?, to)
     } else {
-        let v = v.unwrap().trim();
+        let line = line(lines)?;
+        (line, line)
+    };
+    let range = Range::new(from, to).map_err(|_| err())?;
+
+    Ok((file, Some(range)))
+}
+
+fn parse_token(root: &mut Root, token: &str) -> Result<(), ParseError> {
+    let err = || ParseError::new(ErrorKind::Spec).with(token);
+
+    let mut kv = token.split('=');
+    let (k, v) = (kv.next().unwrap().trim(), kv.next());
+
+    if k.is_empty() || kv.next().is_some() {
+        return Err(err());
+    }
+
+    if let Some(v) = v {
+        let v = v.trim();
         if v.is_empty() {
-            return Err(ParseError::Spec);
+            return Err(err());
         }
 
-        if let Ok(level) = v.to_uppercase().parse() {
-            root.modules.push(Module::with_level(k, level));
+        match v.parse() {
+            Ok(level) => push_module(root, k, level),
+            Err(_) => Err(ParseError::new(ErrorKind::LogLevel).with(v)),
+        }
+    } else {
+        // `k` is either global log level or path for which log level is `TRACE`
+        if let Ok(level) = k.parse() {
+            root.level = Some(level);
+            Ok(())
         } else {
-            return Err(ParseError::Spec);
+            push_module(root, k, LogLevel::TRACE)
         }
     }
+}
 
+fn push_module(root: &mut Root, path: &str, level: LogLevel) -> Result<(), ParseError> {
+    let (path, lines) = parse_lines(path)?;
+    check_path(&path)?;
+    let module = match lines {
+        Some(range) => Module::with_lranges(&path, level, vec![range]),
+        None => Module::with_level(&path, level),
+    };
+    root.modules.push(module);
     Ok(())
 }
 
-fn squash(mut root: Root) -> Result<Root, ParseError> {
+#[doc(hidden)]
+pub fn squash(mut root: Root) -> Result<Root, ParseError> {
     if root.modules.is_empty() {
         return Ok(root);
     }
 
+    // The patterns keep the order of definition since the last defined one wins.
+    let mut order = HashMap::new();
+    for module in &root.modules {
+        if path_pattern::is_pattern(&module.path) {
+            let next = order.len();
+            order.entry(module.path.clone()).or_insert(next);
+        }
+    }
+    let cmp_path = |a: &Module, b: &Module| {
+        match (order.get(&a.path), order.get(&b.path)) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => a.path.cmp(&b.path),
+        }
+    };
+
     root.modules
         .sort_by(|a, b|
-                 match a.path.cmp(&b.path) {
+                 match cmp_path(a, b) {
                      Ordering::Equal => a.level.cmp(&b.level),
                      order => order,
                  }
@@ -307,15 +913,18 @@ fn squash(mut root: Root) -> Result<Root, ParseError> {
         module.lranges = line_range::merge_ranges(&module.lranges, true);
     }
 
-    let mut squashed = Root::new();
-    squashed.level = root.level;
+    let mut squashed = Root {
+        modules: Vec::new(),
+        ..root
+    };
 
     // Squash
     let mut modules = Vec::new();
     let mut iter = root.modules.into_iter();
     let mut prev = iter.next().unwrap();
     for item in iter {
-        if prev.path == item.path && prev.level == item.level  {
+        if prev.path == item.path && prev.level == item.level
+            && prev.handlers == item.handlers && prev.filter == item.filter {
             let mut lranges = Vec::new();
             lranges.extend_from_slice(&prev.lranges);
             lranges.extend_from_slice(&item.lranges);
@@ -331,7 +940,7 @@ fn squash(mut root: Root) -> Result<Root, ParseError> {
     // Make sure that modules without precised line ranges appear first
     squashed.modules
         .sort_by(|a, b|
-                 match a.path.cmp(&b.path) {
+                 match cmp_path(a, b) {
                      Ordering::Equal => {
                          if a.lranges.is_empty() {
                              Ordering::Less
@@ -356,7 +965,8 @@ fn squash(mut root: Root) -> Result<Root, ParseError> {
                     for prange in &prev.lranges {
                         for irange in &item.lranges {
                             if prange.intersects(irange) {
-                                return Err(ParseError::Json(JsonError::Intersection));
+                                return Err(ParseError::new(ErrorKind::Intersection)
+                                           .with(item.path.clone()));
                             }
                         }
                     }
@@ -368,11 +978,13 @@ fn squash(mut root: Root) -> Result<Root, ParseError> {
     Ok(squashed)
 }
 
-#[doc(hidden)]
+/// Parses the log spec.
+///
+/// The spec is either the env_logger spec or the JSON one.
 pub fn parse(spec: &str) -> Result<Root, ParseError> {
     let spec = spec.trim();
     if spec.is_empty() {
-        return Err(ParseError::Spec);
+        return Err(ParseError::new(ErrorKind::Spec));
     }
 
     if spec.starts_with('{') {
@@ -380,28 +992,239 @@ pub fn parse(spec: &str) -> Result<Root, ParseError> {
     }
 
     let mut root = Root::new();
-    for token in spec.split(',') {
-        parse_token(&mut root, token)?;
+    let (rules, filter) = split_filter(spec);
+    if !rules.trim().is_empty() {
+        for (idx, token) in rules.split(',').enumerate() {
+            parse_token(&mut root, token).map_err(|err| err.at(Position::Token(idx)))?;
+            if intersects(&root) {
+                return Err(ParseError::new(ErrorKind::Intersection)
+                           .at(Position::Token(idx))
+                           .with(token.trim()));
+            }
+        }
+    }
+    if let Some(filter) = filter {
+        let idx = rules.matches(',').count();
+        root.filter = Some(Filter::new(filter).map_err(|err| err.at(Position::Token(idx)))?);
     }
 
     squash(root)
 }
 
+// Checks whether the line ranges of the last module intersect
+// with the ones of the same path but a different log level.
+fn intersects(root: &Root) -> bool {
+    let (last, modules) = match root.modules.split_last() {
+        Some((last, modules)) if !last.lranges.is_empty() => (last, modules),
+        _ => return false,
+    };
+    modules.iter()
+        .filter(|module| module.path == last.path && module.level != last.level)
+        .flat_map(|module| module.lranges.iter())
+        .any(|range| last.lranges.iter().any(|lrange| range.intersects(lrange)))
+}
+
+// Splits the env_logger spec into the rules and the message filter.
+//
+// The filter starts at the `/` which either begins the token
+// or follows the log level, e.g. `info/foo` or `bar=debug/foo`.
+// Otherwise the `/` is a part of the file path.
+fn split_filter(spec: &str) -> (&str, Option<&str>) {
+    let mut offset = 0;
+    for token in spec.split(',') {
+        let start = match token.find('=') {
+            Some(idx) => idx + 1,
+            None => 0,
+        };
+        if let Some(idx) = token[start..].find('/') {
+            let level = token[start..start + idx].trim();
+            if level.is_empty() || start > 0 || level.parse::<LogLevel>().is_ok() {
+                let idx = offset + start + idx;
+                return (&spec[..idx], Some(&spec[idx + 1..]));
+            }
+        }
+        offset += token.len() + 1;
+    }
+    (spec, None)
+}
+
+#[doc(hidden)]
+pub fn to_json(root: &Root) -> String {
+    let mut spec = serde_json::Map::new();
+    if let Some(level) = root.level {
+        spec.insert("level".to_string(), level_value(level));
+    }
+    if let Some(ref filter) = root.filter {
+        spec.insert("message".to_string(), Value::from(filter.to_string()));
+    }
+    if !root.modules.is_empty() {
+        let modules = root.modules.iter().map(|module| {
+            let mut object = serde_json::Map::new();
+            object.insert("path".to_string(), Value::from(module.path.clone()));
+            object.insert("level".to_string(), level_value(module.level));
+            if !module.lranges.is_empty() {
+                let lines = module.lranges.iter()
+                    .map(|range| Value::from(vec![range.from, range.to]))
+                    .collect();
+                object.insert("lines".to_string(), Value::Array(lines));
+            }
+            if let Some(ref filter) = module.filter {
+                object.insert("message".to_string(), Value::from(filter.to_string()));
+            }
+            Value::Object(object)
+        }).collect();
+        spec.insert("modules".to_string(), Value::Array(modules));
+    }
+    Value::Object(spec).to_string()
+}
+
+#[doc(hidden)]
+pub fn parse_toml(spec: &str) -> Result<Root, ParseError> {
+    let spec: Value = toml::from_str(spec)
+        .map_err(|err| {
+            let err_msg = syntax_error(&err.to_string());
+            let err = match err.line_col() {
+                Some((line, column)) => ParseError::new(ErrorKind::Toml).at(Position::Line(line + 1, column + 1)),
+                None => ParseError::new(ErrorKind::Toml),
+            };
+            err.with(err_msg)
+        })?;
+    parse_value(&spec).and_then(squash)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use levels;
+
+    fn kind(spec: Result<Root, ParseError>) -> Option<ErrorKind> {
+        spec.err().map(|err| err.kind)
+    }
+
     #[test]
     fn test_spec_invalid() {
-        assert_eq!(Err(ParseError::Spec), parse(""));
-        assert_eq!(Err(ParseError::Spec), parse(","));
-        assert_eq!(Err(ParseError::Spec), parse("="));
-        assert_eq!(Err(ParseError::Spec), parse("foo="));
-        assert_eq!(Err(ParseError::Spec), parse("=error"));
-        assert_eq!(Err(ParseError::Spec), parse("foo,"));
-        assert_eq!(Err(ParseError::Spec), parse(",foo"));
-        assert_eq!(Err(ParseError::Spec), parse("foo=bar"));
-        assert_eq!("Spec", format!("{:?}", parse("foo=bar").err().unwrap()));
+        assert_eq!(Some(ErrorKind::Spec), kind(parse("")));
+        assert_eq!(Some(ErrorKind::Spec), kind(parse(",")));
+        assert_eq!(Some(ErrorKind::Spec), kind(parse("=")));
+        assert_eq!(Some(ErrorKind::Spec), kind(parse("foo=")));
+        assert_eq!(Some(ErrorKind::Spec), kind(parse("=error")));
+        assert_eq!(Some(ErrorKind::Spec), kind(parse("foo,")));
+        assert_eq!(Some(ErrorKind::Spec), kind(parse(",foo")));
+        assert_eq!(Some(ErrorKind::LogLevel), kind(parse("foo=bar")));
+    }
+
+    #[test]
+    fn test_spec_errors() {
+        let err = |spec: &str| {
+            let err = parse(spec).err().unwrap();
+            (err.position.clone(), err.value.clone(), err.to_string())
+        };
+        let pointer = |pointer: &str| Some(Position::Pointer(pointer.to_string()));
+        let value = |value: &str| Some(value.to_string());
+
+        assert_eq!(err("info,foo==debug"),
+                   (Some(Position::Token(1)), value("foo==debug"), r#"invalid spec "foo==debug" at token 1"#.to_string()));
+        assert_eq!(err("foo=debug, =error"),
+                   (Some(Position::Token(1)), value(" =error"), r#"invalid spec " =error" at token 1"#.to_string()));
+        assert_eq!(err("^foo(=debug").0, Some(Position::Token(0)));
+        assert_eq!(err("").2, "invalid spec");
+
+        assert_eq!(err(r#"{"level": 99999}"#),
+                   (pointer("/level"), value("99999"), r#"invalid log level "99999" at /level"#.to_string()));
+        assert_eq!(err(r#"{"modules": [{"path": "foo"}, {"path": "bar", "level": "foo"}]}"#),
+                   (pointer("/modules/1/level"), value("foo"),
+                    r#"invalid log level of the module "foo" at /modules/1/level"#.to_string()));
+        assert_eq!(err(r#"{"modules": [{"path": "bar", "lines": [[1, 2], [20, 10]]}]}"#).0,
+                   pointer("/modules/0/lines/1"));
+        assert_eq!(err(r#"{"modules": [{"level": "info"}]}"#),
+                   (pointer("/modules/0/path"), None, "invalid module at /modules/0/path".to_string()));
+        assert_eq!(err(r#"{"handlers": [{"name": "a", "type": "foo"}]}"#).0, pointer("/handlers/0/type"));
+
+        let (position, value, msg) = err("{\n\"level\": }");
+        assert_eq!(position, Some(Position::Line(2, 10)));
+        assert!(value.is_some());
+        assert!(msg.starts_with("invalid JSON at line 2, column 10: "));
+
+        let err = parse_toml("level = \"info\"\nfoo =").err().unwrap();
+        assert_eq!(err.position, Some(Position::Line(2, 6)));
+
+        let err = parse(r#"{"modules": [{"path": "bar", "lines": [[1, 10]]},
+                                         {"path": "bar", "level": "info", "lines": [[5, 20]]}]}"#).err().unwrap();
+        assert_eq!(err.to_string(), r#"intersecting line ranges with different log levels "[[5,20]]" at /modules/1/lines"#);
+    }
+
+    #[test]
+    fn test_spec_lines() {
+        let expect = Root::with_level(LogLevel::INFO)
+            .module(Module::with_lranges("foo::db@src/db.rs", LogLevel::DEBUG,
+                                         vec!((120, 180).into(), (200, 200).into())))
+            .module(Module::with_lranges("**@src/lib.rs", LogLevel::TRACE,
+                                         vec!((0, u32::MAX).into())))
+            .module(Module::with_lranges("**@src/main.rs", LogLevel::ERROR,
+                                         vec!((10, u32::MAX).into())))
+            .module(Module::with_level("foo::bar", LogLevel::WARN));
+        assert_eq!(Ok(expect), parse("info,foo::db@src/db.rs:120-180=debug,foo::db@src/db.rs:200=debug,\
+                                      src/lib.rs:*,src/main.rs:10-=error,foo::bar=warn"));
+
+        assert_eq!(Some(ErrorKind::LineRange), kind(parse("src/db.rs:20-10=debug")));
+        assert_eq!(Some(ErrorKind::LineRange), kind(parse("src/db.rs:a-10=debug")));
+        assert_eq!(Some(ErrorKind::LineRange), kind(parse("src/db.rs:-10=debug")));
+        assert_eq!(Some(ErrorKind::LineRange), kind(parse("src/db.rs:=debug")));
+        assert_eq!(Some(ErrorKind::Spec), kind(parse(":10=debug")));
+        let err = parse("src/db.rs:10-20=debug,src/db.rs:15=info").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Intersection);
+        assert_eq!(err.position, Some(Position::Token(1)));
+        assert_eq!(err.value, Some("src/db.rs:15=info".to_string()));
+        assert_eq!(Ok(Root::new().module(Module::with_lranges("**@src/db.rs", LogLevel::DEBUG, vec!((10, 20).into())))
+                         .module(Module::with_lranges("**@src/db.rs", LogLevel::INFO, vec!((21, 30).into())))),
+                   parse("src/db.rs:10-20=debug,src/db.rs:21-30=info"));
+
+        let err = parse("info,src/db.rs:x").err().unwrap();
+        assert_eq!(err.to_string(), r#"invalid line range "x" at token 1"#);
+    }
+
+    #[test]
+    fn test_spec_off() {
+        let expect = Root::with_level(LogLevel::TRACE)
+            .module(Module::with_level("noisy", LogLevel::OFF));
+        assert_eq!(Ok(expect.clone()), parse("all,noisy=off"));
+        assert_eq!(Ok(expect), parse(r#"{"level": "all", "modules": [{"path": "noisy", "level": "off"}]}"#));
+    }
+
+    #[test]
+    fn test_spec_filter() {
+        let filter = |f: &str| Some(Filter::new(f).unwrap());
+
+        let mut expect = Root::with_level(LogLevel::INFO)
+            .module(Module::with_level("src/db.rs", LogLevel::DEBUG));
+        expect.filter = filter("a,b=c");
+        assert_eq!(Ok(expect.clone()), parse("info,src/db.rs=debug/a,b=c"));
+        assert_eq!(Ok(expect), parse(r#"{"level": "info", "message": "a,b=c",
+            "modules": [{"path": "src/db.rs", "level": "debug"}]}"#));
+
+        let mut expect = Root::new();
+        expect.filter = filter("!heartbeat");
+        assert_eq!(Ok(expect.clone()), parse("/!heartbeat"));
+        expect.level = Some(LogLevel::WARN);
+        assert_eq!(Ok(expect), parse("warn/!heartbeat"));
+
+        let mut module = Module::with_level("foo", LogLevel::DEBUG);
+        module.filter = filter("^slow");
+        let expect = Root::new().module(module);
+        assert_eq!(Ok(expect.clone()), parse(r#"{"modules": [{"path": "foo", "level": "debug", "message": "^slow"}]}"#));
+        assert_eq!(Ok(expect), parse(&to_json(&parse(r#"{"modules": [{"path": "foo", "level": "debug", "message": "^slow"}]}"#).unwrap())));
+
+        let f = Filter::new("!^slow").unwrap();
+        assert!(f.accepts("fast"));
+        assert!(!f.accepts("slow query"));
+
+        let err = parse("info,foo=debug/(").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Filter);
+        assert_eq!(err.position, Some(Position::Token(1)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Filter)), kind(parse(r#"{"message": 1}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Filter)),
+                   kind(parse(r#"{"modules": [{"path": "a.rs", "lines": [[1, 2]], "message": "x"}]}"#)));
     }
 
     #[test]
@@ -410,6 +1233,21 @@ mod tests {
         assert_eq!(Ok(expect), parse("error"));
     }
 
+    #[test]
+    fn test_spec_pattern() {
+        let expect = Root::new()
+            .module(Module::with_level("*::db::*", LogLevel::DEBUG))
+            .module(Module::with_level("^foo::(bar|qux)", LogLevel::TRACE));
+        assert_eq!(Ok(expect.clone()), parse("*::db::*=debug,^foo::(bar|qux)"));
+        assert_eq!(Ok(expect), parse(r#"{"modules": [
+            {"path": "*::db::*", "level": "debug"},
+            {"path": "^foo::(bar|qux)"}
+        ]}"#));
+
+        assert_eq!(Some(ErrorKind::Pattern), kind(parse("^foo(=debug")));
+        assert_eq!(Some(ErrorKind::Pattern), kind(parse(r#"{"modules": [{"path": "^foo("}]}"#)));
+    }
+
     #[test]
     fn test_spec_module() {
         let expect = Root::new()
@@ -459,44 +1297,148 @@ mod tests {
 
     #[test]
     fn test_spec_json_invalid() {
-        assert_eq!(Err(ParseError::Json(JsonError::Json)), parse("{"));
-
-        assert_eq!(Err(ParseError::Json(JsonError::Root)),
-                   parse(r#"{}"#));
-
-        assert_eq!(Err(ParseError::Json(JsonError::RootLogLevel)),
-                   parse(r#"{"level": "foo"}"#));
-
-        assert_eq!(Err(ParseError::Json(JsonError::Module)),
-                   parse(r#"{"modules": {}}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::Module)),
-                   parse(r#"{"modules": []}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::Module)),
-                   parse(r#"{"modules": [{"level": "critical"}]}"#));
-
-        assert_eq!(Err(ParseError::Json(JsonError::ModuleLogLevel)),
-                   parse(r#"{"modules": [{"path": "bar", "level": "foo"}]}"#));
-
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": {}}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": []}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": [[]]}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": [[1]]}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": [[1, 10, 20]]}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": [[1, 4294967296]]}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": [[4294967296, 4294967297]]}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": [[0, 0.5]]}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": [[0.5, 1]]}]}"#));
-        assert_eq!(Err(ParseError::Json(JsonError::LineRange)),
-                   parse(r#"{"modules": [{"path": "bar", "lines": [[20, 10]]}]}"#));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Json)), kind(parse("{")));
+
+        assert_eq!(Some(ErrorKind::Json(JsonError::Root)),
+                   kind(parse(r#"{}"#)));
+
+        assert_eq!(Some(ErrorKind::Json(JsonError::RootLogLevel)),
+                   kind(parse(r#"{"level": "foo"}"#)));
+
+        assert_eq!(Some(ErrorKind::Json(JsonError::Module)),
+                   kind(parse(r#"{"modules": {}}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Module)),
+                   kind(parse(r#"{"modules": []}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Module)),
+                   kind(parse(r#"{"modules": [{"level": "critical"}]}"#)));
+
+        assert_eq!(Some(ErrorKind::Json(JsonError::ModuleLogLevel)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "level": "foo"}]}"#)));
+
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": {}}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": []}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": [[]]}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": [[1]]}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": [[1, 10, 20]]}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": [[1, 4294967296]]}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": [[4294967296, 4294967297]]}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": [[0, 0.5]]}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": [[0.5, 1]]}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::LineRange)),
+                   kind(parse(r#"{"modules": [{"path": "bar", "lines": [[20, 10]]}]}"#)));
+    }
+
+    #[test]
+    fn test_spec_json_custom_level() {
+        let notable = levels::register("notable", 15).unwrap();
+        let unnamed = LogLevel::CUSTOM(17);
+        let root = Root::with_level(notable)
+            .module(Module::with_level("foo", unnamed))
+            .module(Module::with_level("bar", LogLevel::WARN));
+
+        let json = to_json(&root);
+        assert!(json.contains(r#""level":"NOTABLE""#));
+        assert!(json.contains(r#""level":17"#));
+        assert_eq!(Ok(root), parse(&json));
+
+        let spec = r#"{"modules": [{"path": "foo", "level": 20}]}"#;
+        assert_eq!(Ok(Root::new().module(Module::with_level("foo", LogLevel::WARN))), parse(spec));
+        assert_eq!(Some(ErrorKind::Json(JsonError::ModuleLogLevel)),
+                   kind(parse(r#"{"modules": [{"path": "foo", "level": 1.5}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::ModuleLogLevel)),
+                   kind(parse(r#"{"modules": [{"path": "foo", "level": 50}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::RootLogLevel)), kind(parse(r#"{"level": -20000}"#)));
+        assert_eq!(Ok(Root::with_level(LogLevel::CUSTOM(-9999))), parse(r#"{"level": -9999}"#));
+    }
+
+    #[test]
+    fn test_spec_json_handlers() {
+        let spec = parse(r#"{
+            "formatter": "short",
+            "formatters": {"short": "{level}: {msg}"},
+            "handlers": [
+                {"name": "out", "type": "stdout"},
+                {"name": "err", "type": "stderr", "level": "error", "formatter": "default"},
+                {"name": "db", "type": "rotating_file", "path": "db.log", "count": 3, "size": 1024}
+            ],
+            "modules": [{"path": "foo::db", "handlers": ["db", "err"]}]
+        }"#).unwrap();
+        assert_eq!(spec.formatter, Some("short".to_string()));
+        assert_eq!(spec.pattern("short"), Some("{level}: {msg}"));
+        assert_eq!(spec.handlers, vec![
+            Handler {
+                name: "out".to_string(),
+                kind: HandlerKind::Stdout,
+                level: None,
+                formatter: None,
+            },
+            Handler {
+                name: "err".to_string(),
+                kind: HandlerKind::Stderr,
+                level: Some(LogLevel::ERROR),
+                formatter: Some("default".to_string()),
+            },
+            Handler {
+                name: "db".to_string(),
+                kind: HandlerKind::RotatingFile("db.log".to_string(), 3, 1024),
+                level: None,
+                formatter: None,
+            },
+        ]);
+        assert_eq!(spec.modules[0].handlers, vec!["db".to_string(), "err".to_string()]);
+
+        let handler = |handler: &str| parse(&format!(r#"{{"handlers": [{}]}}"#, handler));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Handler)), kind(handler(r#"{"type": "stdout"}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Handler)), kind(handler(r#"{"name": "a", "type": "foo"}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Handler)), kind(handler(r#"{"name": "a", "type": "file"}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Handler)),
+                   kind(handler(r#"{"name": "a", "type": "rotating_file", "path": "a.log", "size": 1}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Handler)),
+                   kind(handler(r#"{"name": "a", "type": "stdout", "level": "foo"}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Handler)),
+                   kind(handler(r#"{"name": "a", "type": "stdout"}, {"name": "a", "type": "stderr"}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Formatter)),
+                   kind(handler(r#"{"name": "a", "type": "stdout", "formatter": "foo"}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Handler)),
+                   kind(parse(r#"{"modules": [{"path": "foo", "handlers": ["foo"]}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Handler)),
+                   kind(parse(r#"{"handlers": [], "modules": [{"path": "foo", "handlers": []}]}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Formatter)), kind(parse(r#"{"formatter": "foo"}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Formatter)),
+                   kind(parse(r#"{"formatters": {"foo": "{foo}"}}"#)));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Formatter)),
+                   kind(parse(r#"{"formatters": {"default": "{msg}"}}"#)));
+    }
+
+    #[test]
+    fn test_spec_toml() {
+        let expect = Root::with_level(LogLevel::CRITICAL)
+            .module(Module::with_level("foo", LogLevel::TRACE))
+            .module(Module::with_lranges("bar", LogLevel::ERROR, vec!((10, 100).into())));
+        assert_eq!(Ok(expect), parse_toml(r#"
+            level = "critical"
+
+            [[modules]]
+            path = "foo"
+
+            [[modules]]
+            path = "bar"
+            level = "error"
+            lines = [[10, 100]]
+        "#));
+
+        assert_eq!(Some(ErrorKind::Toml), kind(parse_toml("level =")));
+        assert_eq!(Some(ErrorKind::Json(JsonError::Root)), kind(parse_toml("")));
+        assert_eq!(Some(ErrorKind::Json(JsonError::RootLogLevel)), kind(parse_toml("level = -10000")));
     }
 
     #[test]
@@ -559,6 +1501,30 @@ mod tests {
                                      ]
                                 }
                             ]}"#);
-        assert_eq!(Err(ParseError::Json(JsonError::Intersection)), spec)
+        let err = spec.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Json(JsonError::Intersection));
+        assert_eq!(err.position, Some(Position::Pointer("/modules/1/lines".to_string())));
+        assert_eq!(err.value, Some("[[60,70]]".to_string()));
+
+        let err = parse_toml(r#"
+            [[modules]]
+            path = "bar"
+            lines = [[50, 80]]
+
+            [[modules]]
+            path = "foo"
+
+            [[modules]]
+            path = "bar"
+            level = "info"
+            lines = [[10, 20], [60, 70]]
+        "#).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Json(JsonError::Intersection));
+        assert_eq!(err.position, Some(Position::Pointer("/modules/2/lines".to_string())));
+
+        let mut root = Root::new();
+        root.modules.push(Module::with_lranges("bar", LogLevel::TRACE, vec![(50, 80).into()]));
+        root.modules.push(Module::with_lranges("bar", LogLevel::INFO, vec![(60, 70).into()]));
+        assert_eq!(Some(ErrorKind::Intersection), squash(root).err().map(|err| err.kind));
     }
 }
@@ -0,0 +1,184 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Logging statistics.
+//!
+//! The counters are shared by the global logger and the independent loggers.
+//!
+//! The records are counted per callsite with no locking on the logging path.
+//! The time spent on formatting is tracked only once enabled
+//! with [track_formatting](fn.track_formatting.html).
+//!
+//! # Example
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate woodpecker;
+//! use woodpecker as wp;
+//!
+//! fn main() {
+//!     wp_init!();
+//!     wp_register_handler!(Box::new(|_: &dyn wp::Record| {}));
+//!
+//!     wp::stats::reset();
+//!     warn!("foo");
+//!     warn!("bar");
+//!     info!("Not counted since filtered out by the log level");
+//!
+//!     let stats = wp::stats();
+//!     assert_eq!(stats.records, 2);
+//!     assert_eq!(stats.levels, vec![(wp::LogLevel::WARN, 2)]);
+//!     assert_eq!(stats.modules, vec![(module_path!().to_string(), 2)]);
+//! }
+//!
+//! ```
+
+extern crate parking_lot;
+use self::parking_lot::Mutex;
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use formatters::Formatter;
+use levels::LogLevel;
+use logger;
+use record::Record;
+use record::imp::RecordMeta;
+
+static FILTERED: AtomicU64 = AtomicU64::new(0);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static HANDLER_ERRORS: AtomicU64 = AtomicU64::new(0);
+static FORMATTING_NS: AtomicU64 = AtomicU64::new(0);
+static TIMING: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    // The callsites that have produced at least one record.
+    // Locked only on the first record of a callsite.
+    static ref CALLSITES: Mutex<Vec<&'static RecordMeta>> = Mutex::new(Vec::new());
+}
+
+/// Snapshot of the logging statistics.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Stats {
+    /// Number of the log records that passed the log level check.
+    pub records: u64,
+    /// Number of the log records per log level, ordered by the log level.
+    pub levels: Vec<(LogLevel, u64)>,
+    /// Number of the log records per module, ordered by the module path.
+    pub modules: Vec<(String, u64)>,
+    /// Number of the log records rejected by the message filters
+    /// or the [filters](../filters/index.html).
+    pub filtered: u64,
+    /// Number of the log records dropped by the handlers,
+    /// e.g. on the overflow of the [threaded](../handlers/threaded/index.html) handler.
+    pub dropped: u64,
+    /// Number of the log records waiting in the queue of the log thread.
+    pub queue: usize,
+    /// Number of the handler failures, i.e. the I/O errors and panics.
+    pub handler_errors: u64,
+    /// Time spent on formatting the log records.
+    ///
+    /// Stays zero unless enabled with [track_formatting](fn.track_formatting.html).
+    pub formatting: Duration,
+}
+
+/// Returns the logging statistics.
+///
+/// See the [stats](stats/index.html) module for the details.
+pub fn stats() -> Stats {
+    let mut levels = BTreeMap::new();
+    let mut modules = BTreeMap::new();
+    for meta in CALLSITES.lock().iter() {
+        let count = meta.count.load(Ordering::Relaxed);
+        if count > 0 {
+            *levels.entry(meta.level).or_insert(0) += count;
+            *modules.entry(meta.module).or_insert(0) += count;
+        }
+    }
+    let levels: Vec<_> = levels.into_iter().collect();
+    let modules = modules.into_iter().map(|(module, count)| (module.to_string(), count)).collect();
+
+    Stats {
+        records: levels.iter().map(|&(_, count)| count).sum(),
+        levels,
+        modules,
+        filtered: FILTERED.load(Ordering::Relaxed),
+        dropped: DROPPED.load(Ordering::Relaxed),
+        queue: logger::queue_depth(),
+        handler_errors: HANDLER_ERRORS.load(Ordering::Relaxed),
+        formatting: Duration::from_nanos(FORMATTING_NS.load(Ordering::Relaxed)),
+    }
+}
+
+/// Resets the counters.
+///
+/// The depth of the queue isn't a counter and is left as is.
+pub fn reset() {
+    for meta in CALLSITES.lock().iter() {
+        meta.count.store(0, Ordering::Relaxed);
+    }
+    FILTERED.store(0, Ordering::Relaxed);
+    DROPPED.store(0, Ordering::Relaxed);
+    HANDLER_ERRORS.store(0, Ordering::Relaxed);
+    FORMATTING_NS.store(0, Ordering::Relaxed);
+}
+
+/// Enables or disables tracking of the time spent on formatting the log records.
+///
+/// Disabled by default since it costs two clock reads per formatted record.
+pub fn track_formatting(enable: bool) {
+    TIMING.store(enable, Ordering::Relaxed);
+}
+
+#[inline(always)]
+pub(crate) fn record(meta: &'static RecordMeta) {
+    if meta.count.fetch_add(1, Ordering::Relaxed) == 0 {
+        register(meta);
+    }
+}
+
+#[cold]
+fn register(meta: &'static RecordMeta) {
+    if !meta.registered.swap(true, Ordering::Relaxed) {
+        CALLSITES.lock().push(meta);
+    }
+}
+
+#[inline(always)]
+pub(crate) fn filtered() {
+    FILTERED.fetch_add(1, Ordering::Relaxed);
+}
+
+#[inline(always)]
+pub(crate) fn dropped() {
+    DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+#[inline(always)]
+pub(crate) fn handler_error() {
+    HANDLER_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Formats the log record keeping track of the time spent if enabled.
+#[inline(always)]
+pub(crate) fn format(formatter: &Formatter, record: &dyn Record) -> String {
+    if !TIMING.load(Ordering::Relaxed) {
+        return formatter(record);
+    }
+    let start = Instant::now();
+    let formatted = formatter(record);
+    FORMATTING_NS.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    formatted
+}
@@ -0,0 +1,207 @@
+// Copyright 2017 Dmytro Milinevskyi <dmilinevskyi@gmail.com>
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+// http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Log capture and assertion utilities for tests.
+//!
+//! A [Capture](struct.Capture.html) collects the log records emitted by the current thread
+//! while it's alive.
+//! While a capture is active the records of the thread are processed in the thread itself
+//! rather than handed over to the log thread
+//! hence the capture is scoped per test even if the logging in a dedicated thread is enabled.
+//!
+//! The records are captured as the log handlers see them, i.e. after the log level filtering,
+//! the [redaction](../redact/index.html) and the [filters](../filters/index.html),
+//! regardless of the registered log handlers.
+//!
+//! The [wp_assert_logged](../macro.wp_assert_logged.html) and
+//! [wp_assert_not_logged](../macro.wp_assert_not_logged.html) macros
+//! check the records of the innermost capture of the current thread.
+//!
+//! # Example
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate woodpecker;
+//! use woodpecker as wp;
+//!
+//! fn main() {
+//!     wp_init!();
+//!
+//!     let capture = wp::testing::capture();
+//!
+//!     warn!("connection timeout");
+//!     info!("I'm filtered out");
+//!
+//!     wp_assert_logged!(WARN, contains "timeout");
+//!     wp_assert_not_logged!(INFO);
+//!     wp_assert_not_logged!(ERROR);
+//!
+//!     let records = capture.records();
+//!     assert_eq!(records.len(), 1);
+//!     assert_eq!(records[0].msg, "connection timeout");
+//! }
+//!
+//! ```
+
+extern crate time;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use levels::LogLevel;
+use record::Record;
+
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static CAPTURES: RefCell<Vec<Rc<RefCell<Vec<CapturedRecord>>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// An owned copy of a captured log record.
+#[derive(Clone, Debug)]
+pub struct CapturedRecord {
+    /// Log level of the record.
+    pub level: LogLevel,
+    /// Module path.
+    pub module: &'static str,
+    /// File path.
+    pub file: &'static str,
+    /// Line number.
+    pub line: u32,
+    /// Sequence number.
+    pub seq: u64,
+    /// Timestamp.
+    pub ts: time::Timespec,
+    /// Time elapsed since init.
+    pub elapsed: Duration,
+    /// Time elapsed since the previous record of the thread.
+    pub delta: Duration,
+    /// Identifier of the thread.
+    pub thread_id: usize,
+    /// Name of the thread.
+    pub thread_name: Option<String>,
+    /// User log message.
+    pub msg: String,
+    /// Record formatted using the formatter.
+    pub formatted: String,
+}
+
+impl From<&dyn Record> for CapturedRecord {
+    fn from(orig: &dyn Record) -> CapturedRecord {
+        CapturedRecord {
+            level: orig.level(),
+            module: orig.module(),
+            file: orig.file(),
+            line: orig.line(),
+            seq: orig.seq(),
+            ts: orig.ts(),
+            elapsed: orig.elapsed(),
+            delta: orig.delta(),
+            thread_id: orig.thread_id(),
+            thread_name: orig.thread_name().map(str::to_string),
+            msg: orig.msg().to_string(),
+            formatted: orig.formatted().to_string(),
+        }
+    }
+}
+
+/// Captures the log records of the current thread until dropped.
+pub struct Capture {
+    records: Rc<RefCell<Vec<CapturedRecord>>>,
+}
+
+impl Capture {
+    /// Returns a copy of the captured records.
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        self.records.borrow().clone()
+    }
+
+    /// Drops the captured records.
+    pub fn clear(&self) {
+        self.records.borrow_mut().clear();
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        CAPTURES.with(|captures| {
+            captures.borrow_mut().retain(|records| !Rc::ptr_eq(records, &self.records));
+        });
+        ACTIVE.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Starts capturing the log records of the current thread.
+pub fn capture() -> Capture {
+    let records = Rc::new(RefCell::new(Vec::new()));
+    CAPTURES.with(|captures| {
+        captures.borrow_mut().push(records.clone());
+    });
+    ACTIVE.fetch_add(1, Ordering::Relaxed);
+    Capture {
+        records,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed) != 0
+}
+
+// Checks whether the current thread captures the records.
+#[inline(always)]
+pub(crate) fn is_capturing() -> bool {
+    is_active() && CAPTURES.with(|captures| !captures.borrow().is_empty())
+}
+
+pub(crate) fn push(record: &dyn Record) {
+    CAPTURES.with(|captures| {
+        let captures = captures.borrow();
+        if captures.is_empty() {
+            return;
+        }
+        let record = CapturedRecord::from(record);
+        for records in captures.iter() {
+            records.borrow_mut().push(record.clone());
+        }
+    });
+}
+
+#[doc(hidden)]
+pub fn check(logged: bool, level: Option<LogLevel>, needle: Option<&str>) {
+    let records = CAPTURES.with(|captures| {
+        match captures.borrow().last() {
+            Some(records) => records.borrow().clone(),
+            None => panic!("No active log capture"),
+        }
+    });
+    let found = records.iter().any(|record| {
+        level.is_none_or(|level| record.level == level)
+            && needle.is_none_or(|needle| record.msg.contains(needle))
+    });
+    if found != logged {
+        let msgs: Vec<_> = records.iter()
+            .map(|record| format!("{}: {}", record.level, record.msg))
+            .collect();
+        panic!(
+            "Expected a record{}{} to be {}, captured: {:?}",
+            level.map_or(String::new(), |level| format!(" at {}", level)),
+            needle.map_or(String::new(), |needle| format!(" containing {:?}", needle)),
+            if logged { "logged" } else { "not logged" },
+            msgs,
+        );
+    }
+}